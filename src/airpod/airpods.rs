@@ -1,59 +1,104 @@
 use crate::airpod::{
-    Model,
-    packet::{Battery, Color, Flags, Lid, PacketType},
+    BatteryStatus, Capabilities, DecodeError, DecryptedStatus, DeviceView, Model, ProximityKey,
+    Side, encryption,
+    packet::{Battery, Color, Connection, Flags, Lid, PacketType, Status, Tlvs},
+    registry::{self, ModelInfo},
 };
 
-#[repr(C, packed(1))]
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct AirPods {
     pub packet_type: PacketType,
     pub remaining_length: u8,
-    pub unk1: [u8; 1],
+    pub status: Status,
     pub model_id: u16,
     pub flags: Flags,
     pub battery: Battery,
     pub lid: Lid,
-    pub color: Color,
-    pub unk11: u8,
+    pub color_id: u8,
+    pub connection: Connection,
     pub unk12: [u8; 16],
+    /// Bytes sent after the known prefix by newer firmware and models.
+    pub trailing: Vec<u8>,
 }
 
 pub const VENDOR_ID: u16 = 76;
 
-pub fn as_airpods(data: &[u8]) -> Option<AirPods> {
-    if !AirPods::is_valid(data) || data.len() < std::mem::size_of::<AirPods>() {
-        return None;
-    }
-    let mut ap = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const AirPods) };
-    ap.color = Color::from(ap.color as u8);
-    ap.packet_type = PacketType::from(ap.packet_type as u8);
-    Some(ap)
+pub fn as_airpods(data: &[u8]) -> Result<AirPods, DecodeError> {
+    AirPods::from_body(AirPods::find(data)?)
 }
 
 impl AirPods {
+    /// Size of the known part of a proximity pairing message, header included.
+    pub const LEN: usize = 27;
+
+    /// Decodes the body of a proximity pairing message, i.e. the bytes after
+    /// its type and length.
+    pub fn from_body(body: &[u8]) -> Result<AirPods, DecodeError> {
+        if body.len() < Self::LEN - 2 {
+            return Err(DecodeError::InvalidLength {
+                expected: Self::LEN,
+                actual: body.len() + 2,
+            });
+        }
+
+        let mut unk12 = [0u8; 16];
+        unk12.copy_from_slice(&body[9..25]);
+
+        Ok(AirPods {
+            packet_type: PacketType::ProximityPairing,
+            remaining_length: body.len() as u8,
+            status: Status { bits: body[0] },
+            model_id: u16::from_le_bytes([body[1], body[2]]),
+            flags: Flags { bits: body[3] },
+            battery: Battery {
+                bits: body[4],
+                extra: body[5],
+            },
+            lid: Lid { bits: body[6] },
+            color_id: body[7],
+            connection: Connection { bits: body[8] },
+            unk12,
+            trailing: body[25..].to_vec(),
+        })
+    }
+
     pub fn is_valid(data: &[u8]) -> bool {
-        if data.len() != std::mem::size_of::<AirPods>() {
-            return false;
+        as_airpods(data).is_ok()
+    }
+
+    /// Locates the proximity pairing message in Apple manufacturer data and
+    /// returns its body.
+    pub fn find(data: &[u8]) -> Result<&[u8], DecodeError> {
+        if data.is_empty() {
+            return Err(DecodeError::InvalidLength {
+                expected: Self::LEN,
+                actual: 0,
+            });
         }
-        data[0] == PacketType::ProximityPairing as u8 && data[1] == (27 - 2) // remaining length
+
+        for tlv in Tlvs::new(data) {
+            let tlv = tlv?;
+            if tlv.packet_type() == PacketType::ProximityPairing {
+                return Ok(tlv.value);
+            }
+        }
+        Err(DecodeError::NoProximityPairing)
     }
 
     pub fn get_model(model_id: u16) -> Model {
-        match model_id {
-            0x2002 => Model::AirPods1,
-            0x200F => Model::AirPods2,
-            0x2013 => Model::AirPods3,
-            0x200E => Model::AirPodsPro,
-            0x2014 => Model::AirPodsPro2,
-            0x2024 => Model::AirPodsPro2UsbC,
-            0x200A => Model::AirPodsMax,
-            _ => Model::Unknown,
-        }
+        Model::from_id(model_id)
     }
 
     fn broadcast_side(&self) -> bool {
         self.flags.broadcast_from()
     }
+    pub fn broadcasting_side(&self) -> Side {
+        if self.is_left_broadcasted() {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
     pub fn is_left_broadcasted(&self) -> bool {
         self.broadcast_side()
     }
@@ -65,32 +110,57 @@ impl AirPods {
         Self::get_model(self.model_id)
     }
 
-    pub fn left_battery(&self) -> u8 {
+    /// Model details from the global registry, including user-added models.
+    pub fn model_info(&self) -> Option<&'static ModelInfo> {
+        registry::global().get(self.model_id)
+    }
+
+    pub fn model_name(&self) -> String {
+        registry::global().name(self.model_id)
+    }
+
+    /// Capabilities from the global registry, empty for unknown models.
+    pub fn capabilities(&self) -> Capabilities {
+        self.model_info()
+            .map(|info| info.capabilities)
+            .unwrap_or_default()
+    }
+
+    /// Battery and wear state shaped after the device, i.e. a single
+    /// battery for headphones instead of two buds and a case.
+    pub fn view(&self) -> DeviceView {
+        DeviceView::new(self)
+    }
+
+    /// Color name from the global registry, including user-added models.
+    pub fn color_name(&self) -> String {
+        registry::global().color_name(self.model_id, self.color_id)
+    }
+
+    pub fn color(&self) -> Color {
+        Color::for_model(self.model_id, self.color_id)
+    }
+
+    pub fn left_battery(&self) -> BatteryStatus {
         let val = if self.is_left_broadcasted() {
             self.battery.curr()
         } else {
             self.battery.anot()
         };
-        // TODO: make option
-        if val <= 10 { val } else { 0 }
+        BatteryStatus::from_nibble(val, self.is_left_charging())
     }
 
-    pub fn right_battery(&self) -> u8 {
+    pub fn right_battery(&self) -> BatteryStatus {
         let val = if self.is_right_broadcasted() {
             self.battery.curr()
         } else {
             self.battery.anot()
         };
-        // TODO: make option
-        if val <= 10 { val } else { 0 }
+        BatteryStatus::from_nibble(val, self.is_right_charging())
     }
 
-    pub fn case_battery(&self) -> u8 {
-        if self.battery.case_box() <= 10 {
-            self.battery.case_box()
-        } else {
-            0
-        }
+    pub fn case_battery(&self) -> BatteryStatus {
+        BatteryStatus::from_nibble(self.battery.case_box(), self.is_case_charging())
     }
 
     pub fn is_left_charging(&self) -> bool {
@@ -136,28 +206,68 @@ impl AirPods {
             }
     }
 
+    /// Serializes back into manufacturer data, the inverse of `as_airpods`.
+    ///
+    /// Fails if `trailing` is too long for the message length byte.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DecodeError> {
+        let length = Self::LEN - 2 + self.trailing.len();
+        let len = u8::try_from(length).map_err(|_| DecodeError::MessageTooLong { length })?;
+
+        let mut data = Vec::with_capacity(Self::LEN + self.trailing.len());
+        data.push(PacketType::ProximityPairing as u8);
+        data.push(len);
+        data.push(self.status.bits);
+        data.extend_from_slice(&self.model_id.to_le_bytes());
+        data.push(self.flags.bits);
+        data.push(self.battery.bits);
+        data.push(self.battery.extra);
+        data.push(self.lid.bits);
+        data.push(self.color_id);
+        data.push(self.connection.bits);
+        data.extend_from_slice(&self.unk12);
+        data.extend_from_slice(&self.trailing);
+        Ok(data)
+    }
+
+    /// Decrypts the 16-byte tail with the device's proximity key.
+    pub fn decrypt(&self, key: &ProximityKey) -> Result<DecryptedStatus, DecodeError> {
+        encryption::decrypt(self, key)
+    }
+
     pub fn desensitize(&self) -> Self {
-        let mut result = *self;
+        let mut result = self.clone();
         result.unk12 = [0u8; 16];
         result
     }
 
     pub fn debug_info(&self) -> String {
-        let model = self.get_model_instance().as_str();
-        // Battery x10
-        let left_batt = self.left_battery() * 10;
-        let right_batt = self.right_battery() * 10;
-        let case_batt = self.case_battery() * 10;
-        let left_charging = self.is_left_charging();
-        let right_charging = self.is_right_charging();
-        let case_charging = self.is_case_charging();
-        let both_in_case = self.is_both_in_case();
-        let lid_opened = self.is_lid_opened();
-        let left_in_ear = self.is_left_in_ear();
-        let right_in_ear = self.is_right_in_ear();
-        let color = format!("{:?}", self.color);
+        let model = self.model_name();
+        let color = self.color_name();
         let packet_type = format!("{:?}", self.packet_type);
         let remaining_length = self.remaining_length;
+        let state = match self.view() {
+            DeviceView::Earbuds {
+                left,
+                right,
+                case,
+                left_in_ear,
+                right_in_ear,
+                both_in_case,
+                lid_opened,
+            } => format!(
+                "Left Battery: {}\n\
+                Right Battery: {}\n\
+                Case Battery: {}\n\
+                Both in Case: {}\n\
+                Lid Opened: {}\n\
+                Left In Ear: {}\n\
+                Right In Ear: {}",
+                left, right, case, both_in_case, lid_opened, left_in_ear, right_in_ear
+            ),
+            DeviceView::SingleUnit { battery, on_head } => {
+                format!("Battery: {}\nOn Head: {}", battery, on_head)
+            }
+        };
 
         format!(
             "AirPods Debug Info:\n\
@@ -165,29 +275,45 @@ impl AirPods {
             Packet Type: {}\n\
             Remaining Length: {}\n\
             Color: {}\n\
-            Left Battery: {}{}\n\
-            Right Battery: {}{}\n\
-            Case Battery: {}{}\n\
-            Both in Case: {}\n\
-            Lid Opened: {}\n\
-            Left In Ear: {}\n\
-            Right In Ear: {}\n\
-            Desensitized Payload: {:02X?}",
+            {}\n\
+            Status: {:?}\n\
+            Flags: {:?}\n\
+            Lid: {:?}\n\
+            Connection: {:?}\n\
+            Desensitized Payload: {:02X?}\n\
+            Trailing Bytes: {:02X?}",
             model,
             packet_type,
             remaining_length,
             color,
-            left_batt,
-            if left_charging { " (charging)" } else { "" },
-            right_batt,
-            if right_charging { " (charging)" } else { "" },
-            case_batt,
-            if case_charging { " (charging)" } else { "" },
-            both_in_case,
-            lid_opened,
-            left_in_ear,
-            right_in_ear,
-            self.unk12
+            state,
+            self.status,
+            self.flags,
+            self.lid,
+            self.connection,
+            self.unk12,
+            self.trailing
         )
     }
+
+    /// Same as `debug_info`, followed by the exact levels from the encrypted tail.
+    pub fn debug_info_with_key(&self, key: &ProximityKey) -> String {
+        let precise = match (self.decrypt(key), self.view()) {
+            (Ok(status), DeviceView::Earbuds { .. }) => format!(
+                "Precise Left Battery: {}\n\
+                Precise Right Battery: {}\n\
+                Precise Case Battery: {}",
+                status.left, status.right, status.case
+            ),
+            (Ok(status), DeviceView::SingleUnit { .. }) => {
+                let battery = match self.broadcasting_side() {
+                    Side::Left => status.left,
+                    Side::Right => status.right,
+                };
+                format!("Precise Battery: {}", battery)
+            }
+            (Err(err), _) => format!("Decryption Failed: {}", err),
+        };
+        format!("{}\n{}", self.debug_info(), precise)
+    }
 }
@@ -1,30 +1,70 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryLevel {
+    /// Charge in percent.
+    Percent(u8),
+    /// The component is not reporting, e.g. a bud that is out of range.
+    Disconnected,
+    /// A reading outside of the documented range, kept as sent.
+    Unknown(u8),
+}
+
+/// Battery state of a single component (left bud, right bud or case).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Battery(Option<u32>);
+pub struct BatteryStatus {
+    pub level: BatteryLevel,
+    pub charging: bool,
+}
+
+impl BatteryStatus {
+    pub const DISCONNECTED_NIBBLE: u8 = 0xF;
 
-impl Battery {
-    pub fn new() -> Self {
-        Battery(None)
+    /// Builds the status from an advertised 10%-step nibble.
+    pub fn from_nibble(nibble: u8, charging: bool) -> Self {
+        let level = match nibble {
+            0..=10 => BatteryLevel::Percent(nibble * 10),
+            Self::DISCONNECTED_NIBBLE => BatteryLevel::Disconnected,
+            _ => BatteryLevel::Unknown(nibble),
+        };
+        BatteryStatus { level, charging }
     }
-    pub fn from_value(value: u32) -> Self {
-        Battery(Some(value))
+
+    /// Converts back to the advertised nibble, rounding to the nearest 10%.
+    pub fn to_nibble(&self) -> u8 {
+        match self.level {
+            BatteryLevel::Percent(val) => (val.min(100) + 5) / 10,
+            BatteryLevel::Disconnected => Self::DISCONNECTED_NIBBLE,
+            BatteryLevel::Unknown(val) => val & 0x0F,
+        }
     }
 
-    pub fn available(&self) -> bool {
-        self.0.is_some()
+    pub fn percent(&self) -> Option<u8> {
+        match self.level {
+            BatteryLevel::Percent(val) => Some(val),
+            _ => None,
+        }
     }
 
-    pub fn value(&self) -> u32 {
-        self.0.unwrap_or_else(|| {
-            // log warning in Rust style if needed
-            eprintln!("Trying to get the battery value but unavailable.");
-            0
-        })
+    pub fn available(&self) -> bool {
+        self.percent().is_some()
     }
 
     pub fn is_low_battery(&self) -> bool {
-        self.0.map(|v| v <= 20).unwrap_or_else(|| {
-            eprintln!("Trying to determine that the battery is low but unavailable.");
-            false
-        })
+        self.percent().is_some_and(|v| v <= 20)
+    }
+}
+
+impl fmt::Display for BatteryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            BatteryLevel::Percent(val) => write!(f, "{}%", val)?,
+            BatteryLevel::Disconnected => write!(f, "disconnected")?,
+            BatteryLevel::Unknown(val) => write!(f, "unknown (0x{:X})", val)?,
+        }
+        if self.charging {
+            write!(f, " (charging)")?;
+        }
+        Ok(())
     }
 }
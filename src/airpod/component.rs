@@ -0,0 +1,38 @@
+use crate::airpod::Side;
+
+/// A part of a device that reports its own battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+    Left,
+    Right,
+    Case,
+    /// The only battery of a single unit, see `DeviceView::SingleUnit`.
+    Unit,
+}
+
+impl Component {
+    pub const ALL: [Component; 4] = [
+        Component::Left,
+        Component::Right,
+        Component::Case,
+        Component::Unit,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Component::Left => "Left",
+            Component::Right => "Right",
+            Component::Case => "Case",
+            Component::Unit => "Unit",
+        }
+    }
+}
+
+impl From<Side> for Component {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Left => Component::Left,
+            Side::Right => Component::Right,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// AirDrop (0x05), sent while the share sheet is looking for receivers.
+/// The hashes are the first two bytes of SHA-256 of the sender's contacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirDrop {
+    pub version: u8,
+    pub apple_id_hash: [u8; 2],
+    pub phone_hash: [u8; 2],
+    pub email_hash: [u8; 2],
+    pub email2_hash: [u8; 2],
+}
+
+impl AirDrop {
+    pub const LEN: usize = 18;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        Ok(AirDrop {
+            version: data[8],
+            apple_id_hash: [data[9], data[10]],
+            phone_hash: [data[11], data[12]],
+            email_hash: [data[13], data[14]],
+            email2_hash: [data[15], data[16]],
+        })
+    }
+}
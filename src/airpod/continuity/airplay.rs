@@ -0,0 +1,22 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// AirPlay target (0x09), sent by AirPlay receivers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirPlayTarget {
+    pub flags: u8,
+    pub config_seed: u8,
+    pub ipv4: [u8; 4],
+}
+
+impl AirPlayTarget {
+    pub const LEN: usize = 6;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        Ok(AirPlayTarget {
+            flags: data[0],
+            config_seed: data[1],
+            ipv4: [data[2], data[3], data[4], data[5]],
+        })
+    }
+}
@@ -0,0 +1,30 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// AirPrint (0x03), sent by printers advertising their print service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirPrint {
+    pub address_type: u8,
+    pub resource_path_type: u8,
+    pub security_type: u8,
+    pub port: u16,
+    pub address: [u8; 16],
+    pub measured_power: i8,
+}
+
+impl AirPrint {
+    pub const LEN: usize = 22;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        let mut address = [0u8; 16];
+        address.copy_from_slice(&data[5..21]);
+        Ok(AirPrint {
+            address_type: data[0],
+            resource_path_type: data[1],
+            security_type: data[2],
+            port: u16::from_be_bytes([data[3], data[4]]),
+            address,
+            measured_power: data[21] as i8,
+        })
+    }
+}
@@ -0,0 +1,30 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// Handoff (0x0C), sent while an app offers its activity to other devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handoff {
+    pub clipboard_status: u8,
+    pub sequence_number: u16,
+    pub auth_tag: u8,
+    pub encrypted_data: [u8; 10],
+}
+
+impl Handoff {
+    pub const LEN: usize = 14;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        let mut encrypted_data = [0u8; 10];
+        encrypted_data.copy_from_slice(&data[4..14]);
+        Ok(Handoff {
+            clipboard_status: data[0],
+            sequence_number: u16::from_le_bytes([data[1], data[2]]),
+            auth_tag: data[3],
+            encrypted_data,
+        })
+    }
+
+    pub fn has_clipboard(&self) -> bool {
+        self.clipboard_status != 0
+    }
+}
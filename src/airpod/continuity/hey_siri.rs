@@ -0,0 +1,27 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// Hey Siri (0x08), sent by every device that heard the wake word so they
+/// can agree on which one answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeySiri {
+    pub perceptual_hash: u16,
+    pub snr: u8,
+    pub confidence: u8,
+    pub device_class: u16,
+    pub random: u8,
+}
+
+impl HeySiri {
+    pub const LEN: usize = 7;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        Ok(HeySiri {
+            perceptual_hash: u16::from_be_bytes([data[0], data[1]]),
+            snr: data[2],
+            confidence: data[3],
+            device_class: u16::from_be_bytes([data[4], data[5]]),
+            random: data[6],
+        })
+    }
+}
@@ -0,0 +1,30 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// HomeKit (0x06), sent by HomeKit accessories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HomeKit {
+    pub status_flags: u8,
+    pub device_id: [u8; 6],
+    pub category: u16,
+    pub global_state_number: u16,
+    pub configuration_number: u8,
+    pub compatible_version: u8,
+}
+
+impl HomeKit {
+    pub const LEN: usize = 13;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        let mut device_id = [0u8; 6];
+        device_id.copy_from_slice(&data[1..7]);
+        Ok(HomeKit {
+            status_flags: data[0],
+            device_id,
+            category: u16::from_le_bytes([data[7], data[8]]),
+            global_state_number: u16::from_le_bytes([data[9], data[10]]),
+            configuration_number: data[11],
+            compatible_version: data[12],
+        })
+    }
+}
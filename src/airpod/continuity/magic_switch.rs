@@ -0,0 +1,20 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// Magic Switch (0x0B), sent by an Apple Watch that lost its iPhone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MagicSwitch {
+    pub data: [u8; 2],
+    pub confidence_on_wrist: u8,
+}
+
+impl MagicSwitch {
+    pub const LEN: usize = 3;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        Ok(MagicSwitch {
+            data: [data[0], data[1]],
+            confidence_on_wrist: data[2],
+        })
+    }
+}
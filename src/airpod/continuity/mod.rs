@@ -0,0 +1,200 @@
+pub mod airdrop;
+pub mod airplay;
+pub mod airprint;
+pub mod handoff;
+pub mod hey_siri;
+pub mod homekit;
+pub mod magic_switch;
+pub mod nearby_action;
+pub mod nearby_info;
+pub mod tethering;
+
+pub use airdrop::AirDrop;
+pub use airplay::AirPlayTarget;
+pub use airprint::AirPrint;
+pub use handoff::Handoff;
+pub use hey_siri::HeySiri;
+pub use homekit::HomeKit;
+pub use magic_switch::MagicSwitch;
+pub use nearby_action::NearbyAction;
+pub use nearby_info::{Activity, NearbyInfo};
+pub use tethering::{CellType, TetheringSource, TetheringTarget};
+
+use crate::airpod::{
+    AirPods, DecodeError,
+    packet::{PacketType, Tlvs},
+};
+
+/// One Continuity message out of an Apple manufacturer data blob.
+#[derive(Clone)]
+pub enum Message {
+    AirPrint(AirPrint),
+    AirDrop(AirDrop),
+    HomeKit(HomeKit),
+    ProximityPairing(AirPods),
+    HeySiri(HeySiri),
+    AirPlay(AirPlayTarget),
+    MagicSwitch(MagicSwitch),
+    Handoff(Handoff),
+    TetheringTarget(TetheringTarget),
+    TetheringSource(TetheringSource),
+    NearbyAction(NearbyAction),
+    NearbyInfo(NearbyInfo),
+    /// A message of an unknown type, or one too short for its type.
+    Unknown {
+        kind: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl Message {
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            Message::AirPrint(_) => PacketType::AirPrint,
+            Message::AirDrop(_) => PacketType::AirDrop,
+            Message::HomeKit(_) => PacketType::HomeKit,
+            Message::ProximityPairing(_) => PacketType::ProximityPairing,
+            Message::HeySiri(_) => PacketType::HeySiri,
+            Message::AirPlay(_) => PacketType::AirPlay,
+            Message::MagicSwitch(_) => PacketType::MagicSwitch,
+            Message::Handoff(_) => PacketType::Handoff,
+            Message::TetheringTarget(_) => PacketType::InstantHotspotTetheringTargetPresence,
+            Message::TetheringSource(_) => PacketType::InstantHotspotTetheringSourcePresence,
+            Message::NearbyAction(_) => PacketType::NearbyAction,
+            Message::NearbyInfo(_) => PacketType::NearbyInfo,
+            Message::Unknown { kind, .. } => PacketType::from(*kind),
+        }
+    }
+
+    fn decode(kind: u8, data: &[u8]) -> Result<Message, DecodeError> {
+        let message = match PacketType::from(kind) {
+            PacketType::AirPrint => Message::AirPrint(AirPrint::decode(data)?),
+            PacketType::AirDrop => Message::AirDrop(AirDrop::decode(data)?),
+            PacketType::HomeKit => Message::HomeKit(HomeKit::decode(data)?),
+            PacketType::ProximityPairing => Message::ProximityPairing(AirPods::from_body(data)?),
+            PacketType::HeySiri => Message::HeySiri(HeySiri::decode(data)?),
+            PacketType::AirPlay => Message::AirPlay(AirPlayTarget::decode(data)?),
+            PacketType::MagicSwitch => Message::MagicSwitch(MagicSwitch::decode(data)?),
+            PacketType::Handoff => Message::Handoff(Handoff::decode(data)?),
+            PacketType::InstantHotspotTetheringTargetPresence => {
+                Message::TetheringTarget(TetheringTarget::decode(data)?)
+            }
+            PacketType::InstantHotspotTetheringSourcePresence => {
+                Message::TetheringSource(TetheringSource::decode(data)?)
+            }
+            PacketType::NearbyAction => Message::NearbyAction(NearbyAction::decode(data)?),
+            PacketType::NearbyInfo => Message::NearbyInfo(NearbyInfo::decode(data)?),
+            PacketType::Unknown => Message::Unknown {
+                kind,
+                data: data.to_vec(),
+            },
+        };
+        Ok(message)
+    }
+}
+
+/// Splits a company 76 manufacturer data blob into its messages.
+///
+/// A message that is too short for its type is returned as
+/// `Message::Unknown` with its raw bytes. If the TLV framing breaks, the
+/// messages before it are kept and the rest of the blob is returned as a
+/// final `Message::Unknown` holding everything after its type byte.
+pub fn decode(data: &[u8]) -> Vec<Message> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    for tlv in Tlvs::new(data) {
+        let Ok(tlv) = tlv else {
+            messages.push(Message::Unknown {
+                kind: data[offset],
+                data: data[offset + 1..].to_vec(),
+            });
+            break;
+        };
+        offset += 2 + tlv.value.len();
+        let message = Message::decode(tlv.kind, tlv.value).unwrap_or_else(|_| Message::Unknown {
+            kind: tlv.kind,
+            data: tlv.value.to_vec(),
+        });
+        messages.push(message);
+    }
+    messages
+}
+
+/// Checks that a message body holds at least `expected` bytes.
+fn check_len(data: &[u8], expected: usize) -> Result<(), DecodeError> {
+    if data.len() < expected {
+        return Err(DecodeError::InvalidLength {
+            expected,
+            actual: data.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_messages_before_a_broken_one() {
+        let data = [0x10, 0x05, 0x15, 0x1C, 0xAA, 0xBB, 0xCC, 0x07];
+        let messages = decode(&data);
+        assert_eq!(messages.len(), 2);
+
+        let Message::NearbyInfo(info) = &messages[0] else {
+            panic!("expected Nearby Info");
+        };
+        assert_eq!(info.activity(), Activity::AudioPlayingLocked);
+        assert!(info.is_primary_device());
+        assert!(info.is_wifi_on());
+        assert_eq!(info.auth_tag, Some([0xAA, 0xBB, 0xCC]));
+
+        assert!(matches!(
+            &messages[1],
+            Message::Unknown { kind: 0x07, data } if data.is_empty()
+        ));
+    }
+
+    #[test]
+    fn returns_short_and_unknown_messages_raw() {
+        let data = [0x0E, 0x02, 0x01, 0x02, 0x42, 0x01, 0xFF];
+        let messages = decode(&data);
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(
+            &messages[0],
+            Message::Unknown { kind: 0x0E, data } if data == &[0x01, 0x02]
+        ));
+        assert!(matches!(
+            &messages[1],
+            Message::Unknown { kind: 0x42, data } if data == &[0xFF]
+        ));
+        assert_eq!(messages[1].packet_type(), PacketType::Unknown);
+    }
+
+    #[test]
+    fn decodes_a_tethering_source() {
+        let data = [0x0E, 0x06, 0x01, 0x00, 0x57, 0x00, 0x07, 0x03];
+        let messages = decode(&data);
+        let [Message::TetheringSource(source)] = messages.as_slice() else {
+            panic!("expected a tethering source");
+        };
+        assert_eq!(source.battery_percent(), 87);
+        assert_eq!(source.cell_type(), CellType::Lte);
+        assert_eq!(source.to_string(), "87% battery, LTE (3 bars)");
+    }
+
+    #[test]
+    fn decodes_short_nearby_info_frames() {
+        let info = NearbyInfo::decode(&[0x07, 0x00]).unwrap();
+        assert_eq!(info.activity(), Activity::ScreenOn);
+        assert_eq!(info.auth_tag, None);
+        assert!(info.extra.is_empty());
+        assert_eq!(
+            NearbyInfo::decode(&[0x07]),
+            Err(DecodeError::InvalidLength {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// Nearby Action (0x0F), sent by a device asking nearby ones to do
+/// something, e.g. share a Wi-Fi password or set up a new device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearbyAction {
+    pub flags: u8,
+    pub action_type: u8,
+    pub auth_tag: [u8; 3],
+    pub parameters: Vec<u8>,
+}
+
+impl NearbyAction {
+    pub const LEN: usize = 5;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        Ok(NearbyAction {
+            flags: data[0],
+            action_type: data[1],
+            auth_tag: [data[2], data[3], data[4]],
+            parameters: data[5..].to_vec(),
+        })
+    }
+}
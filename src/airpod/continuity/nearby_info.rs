@@ -0,0 +1,94 @@
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// What the user is doing on the device sending a Nearby Info message.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Activity {
+    Unknown = 0xFF,
+    NotKnown = 0x0,
+    ReportingDisabled = 0x1,
+    Idle = 0x3,
+    AudioPlayingLocked = 0x5,
+    ScreenOn = 0x7,
+    VideoPlaying = 0x9,
+    WatchUnlocked = 0xA,
+    RecentInteraction = 0xB,
+    Driving = 0xD,
+    Call = 0xE,
+}
+
+impl From<u8> for Activity {
+    fn from(val: u8) -> Self {
+        match val {
+            0x0 => Activity::NotKnown,
+            0x1 => Activity::ReportingDisabled,
+            0x3 => Activity::Idle,
+            0x5 => Activity::AudioPlayingLocked,
+            0x7 => Activity::ScreenOn,
+            0x9 => Activity::VideoPlaying,
+            0xA => Activity::WatchUnlocked,
+            0xB => Activity::RecentInteraction,
+            0xD => Activity::Driving,
+            0xE => Activity::Call,
+            _ => Activity::Unknown,
+        }
+    }
+}
+
+impl Activity {
+    /// Whether the device is playing or recording audio, in which case
+    /// the user's AirPods are likely routed to it.
+    pub fn is_audio_active(&self) -> bool {
+        matches!(
+            self,
+            Activity::AudioPlayingLocked | Activity::VideoPlaying | Activity::Call
+        )
+    }
+}
+
+/// Nearby Info (0x10), sent continuously by iPhones, iPads and Macs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearbyInfo {
+    pub status_flags: u8,
+    pub action_code: u8,
+    pub data_flags: u8,
+    /// Missing in the short frames some older devices send.
+    pub auth_tag: Option<[u8; 3]>,
+    pub extra: Vec<u8>,
+}
+
+impl NearbyInfo {
+    pub const LEN: usize = 2;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        let auth_tag = data.get(2..5).map(|tag| [tag[0], tag[1], tag[2]]);
+        let extra = data.get(5..).unwrap_or_default().to_vec();
+        Ok(NearbyInfo {
+            status_flags: data[0] >> 4,
+            action_code: data[0] & 0x0F,
+            data_flags: data[1],
+            auth_tag,
+            extra,
+        })
+    }
+
+    pub fn activity(&self) -> Activity {
+        Activity::from(self.action_code)
+    }
+
+    pub fn is_primary_device(&self) -> bool {
+        self.status_flags & 0b0001 != 0
+    }
+    pub fn is_airdrop_receiving(&self) -> bool {
+        self.status_flags & 0b0100 != 0
+    }
+    pub fn is_wifi_on(&self) -> bool {
+        self.data_flags & 0b0000_0100 != 0
+    }
+
+    /// Whether the sender is currently playing audio or on a call.
+    pub fn is_audio_active(&self) -> bool {
+        self.activity().is_audio_active()
+    }
+}
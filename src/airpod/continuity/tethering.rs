@@ -0,0 +1,115 @@
+use std::fmt;
+
+use crate::airpod::{DecodeError, continuity::check_len};
+
+/// Cellular technology reported by an Instant Hotspot source.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CellType {
+    Unknown = 0xFF,
+    NoService = 0x0,
+    OneXRtt = 0x1,
+    Gprs = 0x2,
+    Edge = 0x3,
+    EvDo = 0x4,
+    ThreeG = 0x5,
+    FourG = 0x6,
+    Lte = 0x7,
+}
+
+impl From<u8> for CellType {
+    fn from(val: u8) -> Self {
+        match val {
+            0x0 => CellType::NoService,
+            0x1 => CellType::OneXRtt,
+            0x2 => CellType::Gprs,
+            0x3 => CellType::Edge,
+            0x4 => CellType::EvDo,
+            0x5 => CellType::ThreeG,
+            0x6 => CellType::FourG,
+            0x7 => CellType::Lte,
+            _ => CellType::Unknown,
+        }
+    }
+}
+
+impl CellType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CellType::NoService => "No Service",
+            CellType::OneXRtt => "1xRTT",
+            CellType::Gprs => "GPRS",
+            CellType::Edge => "EDGE",
+            CellType::EvDo => "3G (EV-DO)",
+            CellType::ThreeG => "3G",
+            CellType::FourG => "4G",
+            CellType::Lte => "LTE",
+            CellType::Unknown => "Unknown",
+        }
+    }
+}
+
+/// Instant Hotspot target presence (0x0D), sent by devices that could use
+/// a hotspot on the same iCloud account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TetheringTarget {
+    pub identifier: [u8; 4],
+}
+
+impl TetheringTarget {
+    pub const LEN: usize = 4;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        Ok(TetheringTarget {
+            identifier: [data[0], data[1], data[2], data[3]],
+        })
+    }
+}
+
+/// Instant Hotspot source presence (0x0E), sent by a phone offering its
+/// cellular connection to devices on the same iCloud account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TetheringSource {
+    pub version: u8,
+    pub flags: u8,
+    pub battery: u8,
+    pub cell_type: u8,
+    pub cell_bars: u8,
+}
+
+impl TetheringSource {
+    pub const LEN: usize = 6;
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        check_len(data, Self::LEN)?;
+        Ok(TetheringSource {
+            version: data[0],
+            flags: data[1],
+            battery: data[2],
+            cell_type: data[4],
+            cell_bars: data[5],
+        })
+    }
+
+    /// Battery of the phone in percent.
+    pub fn battery_percent(&self) -> u8 {
+        self.battery.min(100)
+    }
+
+    pub fn cell_type(&self) -> CellType {
+        CellType::from(self.cell_type)
+    }
+}
+
+impl fmt::Display for TetheringSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}% battery, {} ({} bars)",
+            self.battery_percent(),
+            self.cell_type().as_str(),
+            self.cell_bars
+        )
+    }
+}
@@ -0,0 +1,161 @@
+use aes::{
+    Aes128,
+    cipher::{Array, BlockCipherDecrypt, KeyInit},
+};
+
+use crate::airpod::{AirPods, BatteryLevel, BatteryStatus, DecodeError};
+
+/// Proximity encryption key of a paired device (ENC_KEY), used to decrypt
+/// the 16-byte tail of its proximity pairing messages.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ProximityKey(pub [u8; 16]);
+
+impl ProximityKey {
+    /// Parses 32 hex digits, optionally separated by spaces, colons or dashes.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        parse_key_hex(s).map(ProximityKey)
+    }
+}
+
+pub(crate) fn parse_key_hex(s: &str) -> Option<[u8; 16]> {
+    let digits: Vec<u8> = s
+        .bytes()
+        .filter(|b| !matches!(b, b' ' | b':' | b'-'))
+        .collect();
+    if digits.len() != 32 {
+        return None;
+    }
+    let mut key = [0u8; 16];
+    for (byte, pair) in key.iter_mut().zip(digits.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(key)
+}
+
+/// Exact battery levels carried by the encrypted tail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecryptedStatus {
+    pub left: BatteryStatus,
+    pub right: BatteryStatus,
+    pub case: BatteryStatus,
+    pub plaintext: [u8; 16],
+}
+
+const LEVEL_UNAVAILABLE: u8 = 0x7F;
+
+/// Decrypts the encrypted tail and checks it against the plain 10%-step
+/// levels, so a wrong key is reported instead of yielding random levels.
+pub fn decrypt(ap: &AirPods, key: &ProximityKey) -> Result<DecryptedStatus, DecodeError> {
+    let cipher = Aes128::new(&Array::from(key.0));
+    let mut block = Array::from(ap.unk12);
+    cipher.decrypt_block(&mut block);
+    let plaintext: [u8; 16] = block.into();
+
+    let status = DecryptedStatus {
+        left: precise_level(&plaintext, 1, ap.left_battery())?,
+        right: precise_level(&plaintext, 2, ap.right_battery())?,
+        case: precise_level(&plaintext, 3, ap.case_battery())?,
+        plaintext,
+    };
+    Ok(status)
+}
+
+fn precise_level(
+    plaintext: &[u8; 16],
+    offset: usize,
+    coarse: BatteryStatus,
+) -> Result<BatteryStatus, DecodeError> {
+    let value = plaintext[offset];
+    let charging = value & 0x80 != 0;
+    let level = value & 0x7F;
+    let invalid = DecodeError::InvalidPlaintext { offset, value };
+
+    if level == LEVEL_UNAVAILABLE {
+        return Ok(BatteryStatus {
+            level: BatteryLevel::Disconnected,
+            charging,
+        });
+    }
+    if level > 100 {
+        return Err(invalid);
+    }
+    if coarse
+        .percent()
+        .is_some_and(|coarse| level.abs_diff(coarse) > 10)
+    {
+        return Err(invalid);
+    }
+    Ok(BatteryStatus {
+        level: BatteryLevel::Percent(level),
+        charging,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airpod::{AirPodsState, as_airpods};
+    use aes::cipher::BlockCipherEncrypt;
+
+    const KEY: ProximityKey = ProximityKey([0x5A; 16]);
+
+    /// Sample AirPods whose encrypted tail holds `plaintext` under `KEY`.
+    fn airpods(plaintext: [u8; 16]) -> AirPods {
+        let mut ap = as_airpods(&AirPodsState::sample().encode()).unwrap();
+        let mut block = Array::from(plaintext);
+        Aes128::new(&Array::from(KEY.0)).encrypt_block(&mut block);
+        ap.unk12 = block.into();
+        ap
+    }
+
+    #[test]
+    fn decrypts_precise_levels() {
+        let mut plaintext = [0u8; 16];
+        plaintext[1..4].copy_from_slice(&[83, 0x80 | 88, LEVEL_UNAVAILABLE]);
+
+        let status = airpods(plaintext).decrypt(&KEY).unwrap();
+        assert_eq!(status.plaintext, plaintext);
+        assert_eq!(
+            status.left,
+            BatteryStatus {
+                level: BatteryLevel::Percent(83),
+                charging: false
+            }
+        );
+        assert_eq!(
+            status.right,
+            BatteryStatus {
+                level: BatteryLevel::Percent(88),
+                charging: true
+            }
+        );
+        assert_eq!(status.case.level, BatteryLevel::Disconnected);
+    }
+
+    #[test]
+    fn rejects_levels_far_from_the_coarse_ones() {
+        let mut plaintext = [0u8; 16];
+        // The sample's left bud reads 80%
+        plaintext[1..4].copy_from_slice(&[30, 90, 50]);
+        assert_eq!(
+            airpods(plaintext).decrypt(&KEY),
+            Err(DecodeError::InvalidPlaintext {
+                offset: 1,
+                value: 30
+            })
+        );
+    }
+
+    #[test]
+    fn reports_a_wrong_key() {
+        let mut plaintext = [0u8; 16];
+        plaintext[1..4].copy_from_slice(&[80, 90, 50]);
+        let ap = airpods(plaintext);
+
+        assert!(ap.decrypt(&KEY).is_ok());
+        assert!(matches!(
+            ap.decrypt(&ProximityKey([0xA5; 16])),
+            Err(DecodeError::InvalidPlaintext { .. })
+        ));
+    }
+}
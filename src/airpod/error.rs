@@ -0,0 +1,50 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The payload is too short for the message it should hold.
+    InvalidLength { expected: usize, actual: usize },
+    /// None of the messages in the payload is a proximity pairing message.
+    NoProximityPairing,
+    /// A length byte claims more bytes than follow it.
+    InvalidRemainingLength { declared: u8, available: usize },
+    /// The decrypted tail does not hold plausible values, usually a wrong key.
+    InvalidPlaintext { offset: usize, value: u8 },
+    /// A message to encode does not fit its one-byte length.
+    MessageTooLong { length: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLength { expected, actual } => {
+                write!(
+                    f,
+                    "invalid length: expected at least {} bytes, got {}",
+                    expected, actual
+                )
+            }
+            DecodeError::NoProximityPairing => {
+                write!(f, "no proximity pairing message in payload")
+            }
+            DecodeError::InvalidRemainingLength {
+                declared,
+                available,
+            } => write!(
+                f,
+                "invalid remaining length: declared {} bytes, {} available",
+                declared, available
+            ),
+            DecodeError::InvalidPlaintext { offset, value } => write!(
+                f,
+                "invalid plaintext: byte {} is 0x{:02X}, wrong key?",
+                offset, value
+            ),
+            DecodeError::MessageTooLong { length } => {
+                write!(f, "message too long: {} bytes, at most 255", length)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
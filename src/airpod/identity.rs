@@ -0,0 +1,106 @@
+use aes::{
+    Aes128,
+    cipher::{Array, BlockCipherEncrypt, KeyInit},
+};
+
+use crate::airpod::encryption::parse_key_hex;
+
+/// Identity Resolving Key, most significant byte first as in the
+/// Bluetooth Core specification.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Irk(pub [u8; 16]);
+
+impl Irk {
+    /// Parses 32 hex digits, optionally separated by spaces, colons or dashes.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        parse_key_hex(s).map(Irk)
+    }
+
+    /// Random part function `ah` (Core spec Vol 3, Part H, 2.2.2).
+    pub fn ah(&self, prand: u32) -> u32 {
+        let mut block = [0u8; 16];
+        block[13..].copy_from_slice(&prand.to_be_bytes()[1..]);
+
+        let cipher = Aes128::new(&Array::from(self.0));
+        let mut block = Array::from(block);
+        cipher.encrypt_block(&mut block);
+        u32::from_be_bytes([0, block[13], block[14], block[15]])
+    }
+
+    /// Checks whether `address` is a resolvable private address generated
+    /// from this key.
+    pub fn resolves(&self, address: u64) -> bool {
+        if !is_resolvable_private_address(address) {
+            return false;
+        }
+        let prand = ((address >> 24) & 0xFF_FFFF) as u32;
+        let hash = (address & 0xFF_FFFF) as u32;
+        self.ah(prand) == hash
+    }
+}
+
+/// A resolvable private address has `0b01` as its two most significant bits.
+pub fn is_resolvable_private_address(address: u64) -> bool {
+    (address >> 46) & 0b11 == 0b01
+}
+
+/// Maps advertiser addresses to stable identities through their IRKs.
+#[derive(Clone)]
+pub struct IdentityResolver<T> {
+    identities: Vec<(T, Irk)>,
+}
+
+impl<T> Default for IdentityResolver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IdentityResolver<T> {
+    pub fn new() -> Self {
+        IdentityResolver {
+            identities: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, identity: T, irk: Irk) {
+        self.identities.push((identity, irk));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.identities.is_empty()
+    }
+
+    /// Returns the identity whose key generated `address`, if any.
+    pub fn resolve(&self, address: u64) -> Option<&T> {
+        self.identities
+            .iter()
+            .find(|(_, irk)| irk.resolves(address))
+            .map(|(identity, _)| identity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sample data of the Core spec (Vol 3, Part H, D.7).
+    const IRK: &str = "ec0234a357c8ad05341010a60a397d9b";
+
+    #[test]
+    fn matches_the_spec_sample_data() {
+        let irk = Irk::from_hex(IRK).unwrap();
+        assert_eq!(irk.ah(0x70_8194), 0x0D_FBAA);
+        assert!(irk.resolves(0x7081_940D_FBAA));
+    }
+
+    #[test]
+    fn resolves_only_its_own_private_addresses() {
+        let irk = Irk::from_hex(IRK).unwrap();
+        // Same bits but a static random address
+        assert!(!is_resolvable_private_address(0xF081_940D_FBAA));
+        assert!(!irk.resolves(0xF081_940D_FBAA));
+        // A private address with another hash
+        assert!(!irk.resolves(0x7081_940D_FBAB));
+    }
+}
@@ -1,8 +1,25 @@
 pub mod airpods;
+pub mod battery;
+pub mod component;
+pub mod continuity;
+pub mod encryption;
+pub mod error;
+pub mod identity;
 pub mod model;
 pub mod packet;
+pub mod registry;
 pub mod side;
+pub mod state;
+pub mod view;
 
 pub use airpods::{AirPods, VENDOR_ID, as_airpods};
-pub use model::Model;
+pub use battery::{BatteryLevel, BatteryStatus};
+pub use component::Component;
+pub use encryption::{DecryptedStatus, ProximityKey};
+pub use error::DecodeError;
+pub use identity::{IdentityResolver, Irk};
+pub use model::{Capabilities, Family, Model};
+pub use registry::{ModelInfo, Registry};
 pub use side::Side;
+pub use state::AirPodsState;
+pub use view::DeviceView;
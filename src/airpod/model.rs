@@ -1,3 +1,29 @@
+use serde::Deserialize;
+
+use crate::airpod::registry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Family {
+    AirPods,
+    Beats,
+}
+
+/// What a model supports, so callers don't have to guess from its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    pub anc: bool,
+    pub adaptive_audio: bool,
+    pub conversational_awareness: bool,
+    /// The case reports its own battery level.
+    pub case_battery: bool,
+    /// The case has a speaker for Find My.
+    pub case_speaker: bool,
+    /// One unit with a single battery, e.g. over-ear headphones or a
+    /// neckband, instead of two buds in a case.
+    pub single_unit: bool,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Model {
     Unknown = 0,
@@ -7,10 +33,53 @@ pub enum Model {
     AirPodsPro,
     AirPodsPro2,
     AirPodsPro2UsbC,
+    AirPodsPro3,
+    AirPods4,
+    AirPods4Anc,
     AirPodsMax,
+    AirPodsMaxUsbC,
+    Powerbeats3,
+    Powerbeats4,
+    PowerbeatsPro,
+    BeatsX,
+    BeatsFlex,
+    BeatsSolo3,
+    BeatsSoloPro,
+    BeatsStudio3,
+    BeatsStudioPro,
+    BeatsStudioBuds,
+    BeatsStudioBudsPlus,
+    BeatsFitPro,
 }
 
 impl Model {
+    /// Every known model, `Unknown` excluded.
+    pub const ALL: &[Model] = &[
+        Model::AirPods1,
+        Model::AirPods2,
+        Model::AirPods3,
+        Model::AirPodsPro,
+        Model::AirPodsPro2,
+        Model::AirPodsPro2UsbC,
+        Model::AirPodsPro3,
+        Model::AirPods4,
+        Model::AirPods4Anc,
+        Model::AirPodsMax,
+        Model::AirPodsMaxUsbC,
+        Model::Powerbeats3,
+        Model::Powerbeats4,
+        Model::PowerbeatsPro,
+        Model::BeatsX,
+        Model::BeatsFlex,
+        Model::BeatsSolo3,
+        Model::BeatsSoloPro,
+        Model::BeatsStudio3,
+        Model::BeatsStudioPro,
+        Model::BeatsStudioBuds,
+        Model::BeatsStudioBudsPlus,
+        Model::BeatsFitPro,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Model::AirPods1 => "AirPods 1",
@@ -19,8 +88,83 @@ impl Model {
             Model::AirPodsPro => "AirPods Pro",
             Model::AirPodsPro2 => "AirPods Pro 2",
             Model::AirPodsPro2UsbC => "AirPods Pro 2 (USB-C)",
+            Model::AirPodsPro3 => "AirPods Pro 3",
+            Model::AirPods4 => "AirPods 4",
+            Model::AirPods4Anc => "AirPods 4 (ANC)",
             Model::AirPodsMax => "AirPods Max",
+            Model::AirPodsMaxUsbC => "AirPods Max (USB-C)",
+            Model::Powerbeats3 => "Powerbeats3",
+            Model::Powerbeats4 => "Powerbeats",
+            Model::PowerbeatsPro => "Powerbeats Pro",
+            Model::BeatsX => "BeatsX",
+            Model::BeatsFlex => "Beats Flex",
+            Model::BeatsSolo3 => "Beats Solo3",
+            Model::BeatsSoloPro => "Beats Solo Pro",
+            Model::BeatsStudio3 => "Beats Studio3",
+            Model::BeatsStudioPro => "Beats Studio Pro",
+            Model::BeatsStudioBuds => "Beats Studio Buds",
+            Model::BeatsStudioBudsPlus => "Beats Studio Buds +",
+            Model::BeatsFitPro => "Beats Fit Pro",
             Model::Unknown => "Unknown",
         }
     }
+
+    /// Model id as advertised, the inverse of `AirPods::get_model`.
+    pub fn id(&self) -> u16 {
+        match self {
+            Model::AirPods1 => 0x2002,
+            Model::AirPods2 => 0x200F,
+            Model::AirPods3 => 0x2013,
+            Model::AirPodsPro => 0x200E,
+            Model::AirPodsPro2 => 0x2014,
+            Model::AirPodsPro2UsbC => 0x2024,
+            Model::AirPodsPro3 => 0x2027,
+            Model::AirPods4 => 0x2019,
+            Model::AirPods4Anc => 0x201B,
+            Model::AirPodsMax => 0x200A,
+            Model::AirPodsMaxUsbC => 0x201F,
+            Model::Powerbeats3 => 0x2003,
+            Model::Powerbeats4 => 0x200D,
+            Model::PowerbeatsPro => 0x200B,
+            Model::BeatsX => 0x2005,
+            Model::BeatsFlex => 0x2010,
+            Model::BeatsSolo3 => 0x2006,
+            Model::BeatsSoloPro => 0x200C,
+            Model::BeatsStudio3 => 0x2009,
+            Model::BeatsStudioPro => 0x2017,
+            Model::BeatsStudioBuds => 0x2011,
+            Model::BeatsStudioBudsPlus => 0x2016,
+            Model::BeatsFitPro => 0x2012,
+            Model::Unknown => 0x0000,
+        }
+    }
+
+    /// Display name from the global registry, so names from a models file
+    /// take precedence over `as_str`.
+    pub fn name(&self) -> String {
+        registry::global().name(self.id())
+    }
+
+    /// Model with the given id, `Unknown` for ids that are not compiled in.
+    /// Those can still be in the registry, see `AirPods::model_info`.
+    pub fn from_id(model_id: u16) -> Model {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|model| model.id() == model_id)
+            .unwrap_or(Model::Unknown)
+    }
+
+    /// Family from the global registry, `None` for unknown models.
+    pub fn family(&self) -> Option<Family> {
+        registry::global().get(self.id())?.family
+    }
+
+    /// Capabilities from the global registry, empty for unknown models.
+    pub fn capabilities(&self) -> Capabilities {
+        registry::global()
+            .get(self.id())
+            .map(|info| info.capabilities)
+            .unwrap_or_default()
+    }
 }
@@ -24,4 +24,31 @@ impl Battery {
     pub fn case_charging(&self) -> bool {
         (self.extra & 0b0100_0000) != 0
     }
+
+    pub fn set_curr(&mut self, val: u8) {
+        self.bits = (self.bits & 0xF0) | (val & 0x0F);
+    }
+    pub fn set_anot(&mut self, val: u8) {
+        self.bits = (self.bits & 0x0F) | ((val & 0x0F) << 4);
+    }
+    pub fn set_case_box(&mut self, val: u8) {
+        self.extra = (self.extra & 0xF0) | (val & 0x0F);
+    }
+    pub fn set_curr_charging(&mut self, val: bool) {
+        self.set_extra(0b0001_0000, val);
+    }
+    pub fn set_anot_charging(&mut self, val: bool) {
+        self.set_extra(0b0010_0000, val);
+    }
+    pub fn set_case_charging(&mut self, val: bool) {
+        self.set_extra(0b0100_0000, val);
+    }
+
+    fn set_extra(&mut self, mask: u8, val: bool) {
+        if val {
+            self.extra |= mask;
+        } else {
+            self.extra &= !mask;
+        }
+    }
 }
@@ -1,3 +1,7 @@
+use crate::airpod::registry;
+
+/// Color of a device. The discriminants are the shared palette decoded by
+/// `Color::from`; Beats models use their own bytes, see `Color::for_model`.
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Color {
@@ -15,6 +19,16 @@ pub enum Color {
     DarkBlue = 0xA,
     LightBlue = 0xB,
     Yellow = 0xC,
+    // Beats only
+    Ivory,
+    Moss,
+    Navy,
+    Purple,
+    Coral,
+    Sand,
+    Brown,
+    Transparent,
+    BlackGold,
 }
 
 impl From<u8> for Color {
@@ -37,3 +51,82 @@ impl From<u8> for Color {
         }
     }
 }
+
+impl Color {
+    /// Every named color, `Unknown` excluded.
+    pub const ALL: &[Color] = &[
+        Color::White,
+        Color::Black,
+        Color::Red,
+        Color::Blue,
+        Color::Pink,
+        Color::Gray,
+        Color::Silver,
+        Color::Gold,
+        Color::RoseGold,
+        Color::SpaceGray,
+        Color::DarkBlue,
+        Color::LightBlue,
+        Color::Yellow,
+        Color::Ivory,
+        Color::Moss,
+        Color::Navy,
+        Color::Purple,
+        Color::Coral,
+        Color::Sand,
+        Color::Brown,
+        Color::Transparent,
+        Color::BlackGold,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Color::White => "White",
+            Color::Black => "Black",
+            Color::Red => "Red",
+            Color::Blue => "Blue",
+            Color::Pink => "Pink",
+            Color::Gray => "Gray",
+            Color::Silver => "Silver",
+            Color::Gold => "Gold",
+            Color::RoseGold => "Rose Gold",
+            Color::SpaceGray => "Space Gray",
+            Color::DarkBlue => "Dark Blue",
+            Color::LightBlue => "Light Blue",
+            Color::Yellow => "Yellow",
+            Color::Ivory => "Ivory",
+            Color::Moss => "Moss",
+            Color::Navy => "Navy",
+            Color::Purple => "Purple",
+            Color::Coral => "Coral",
+            Color::Sand => "Sand",
+            Color::Brown => "Brown",
+            Color::Transparent => "Transparent",
+            Color::BlackGold => "Black/Gold",
+            Color::Unknown => "Unknown",
+        }
+    }
+
+    /// Color named `name` in a models file, `Unknown` for names that are
+    /// not a variant.
+    pub fn from_name(name: &str) -> Self {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|color| color.as_str() == name)
+            .unwrap_or(Color::Unknown)
+    }
+
+    /// Decodes a color byte for the given model id from the global
+    /// registry. Each Beats model has its own palette, ids missing from the
+    /// registry use the shared palette of `Color::from`.
+    pub fn for_model(model_id: u16, val: u8) -> Self {
+        registry::global().color(model_id, val)
+    }
+
+    /// Byte advertised by the given model id for this color, the inverse of
+    /// `for_model`. `None` if the model doesn't come in this color.
+    pub fn id_for(&self, model_id: u16) -> Option<u8> {
+        registry::global().color_id(model_id, *self)
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+/// What the buds are doing with the device they are connected to.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConnectionState {
+    Unknown = 0xFF,
+    Disconnected = 0x00,
+    Idle = 0x04,
+    Music = 0x05,
+    Call = 0x06,
+    Ringing = 0x07,
+    HangingUp = 0x09,
+}
+
+impl From<u8> for ConnectionState {
+    fn from(val: u8) -> Self {
+        match val {
+            0x00 => ConnectionState::Disconnected,
+            0x04 => ConnectionState::Idle,
+            0x05 => ConnectionState::Music,
+            0x06 => ConnectionState::Call,
+            0x07 => ConnectionState::Ringing,
+            0x09 => ConnectionState::HangingUp,
+            _ => ConnectionState::Unknown,
+        }
+    }
+}
+
+/// Connection state byte, right after the color.
+#[repr(C, packed(1))]
+#[derive(Clone, Copy)]
+pub struct Connection {
+    pub bits: u8,
+}
+
+impl Connection {
+    pub fn state(&self) -> ConnectionState {
+        ConnectionState::from(self.bits)
+    }
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.state() {
+            ConnectionState::Unknown => write!(f, "Unknown({:#04X})", self.bits),
+            state => write!(f, "{:?}", state),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[repr(C, packed(1))]
 #[derive(Clone, Copy)]
 pub struct Flags {
@@ -14,7 +16,67 @@ impl Flags {
     pub fn anot_in_ear(&self) -> bool {
         self.bits & 0b0000_1000 != 0
     }
+    /// Exactly one bud is in the case.
+    pub fn one_in_case(&self) -> bool {
+        self.bits & 0b0001_0000 != 0
+    }
+    /// The primary bud is the left one, which is also the side whose
+    /// values are in the `curr` fields.
     pub fn broadcast_from(&self) -> bool {
         self.bits & 0b0010_0000 != 0
     }
+    pub fn primary_left(&self) -> bool {
+        self.broadcast_from()
+    }
+    /// The broadcasting bud sits in the case.
+    pub fn curr_in_case(&self) -> bool {
+        self.bits & 0b0100_0000 != 0
+    }
+    pub fn unknown_bits(&self) -> u8 {
+        self.bits & 0b1000_0001
+    }
+
+    pub fn set_curr_in_ear(&mut self, val: bool) {
+        self.set(0b0000_0010, val);
+    }
+    pub fn set_both_in_case(&mut self, val: bool) {
+        self.set(0b0000_0100, val);
+    }
+    pub fn set_anot_in_ear(&mut self, val: bool) {
+        self.set(0b0000_1000, val);
+    }
+    pub fn set_one_in_case(&mut self, val: bool) {
+        self.set(0b0001_0000, val);
+    }
+    pub fn set_broadcast_from(&mut self, val: bool) {
+        self.set(0b0010_0000, val);
+    }
+    pub fn set_curr_in_case(&mut self, val: bool) {
+        self.set(0b0100_0000, val);
+    }
+
+    fn set(&mut self, mask: u8, val: bool) {
+        if val {
+            self.bits |= mask;
+        } else {
+            self.bits &= !mask;
+        }
+    }
+}
+
+impl fmt::Debug for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Flags")
+            .field("curr_in_ear", &self.curr_in_ear())
+            .field("anot_in_ear", &self.anot_in_ear())
+            .field("both_in_case", &self.both_in_case())
+            .field("one_in_case", &self.one_in_case())
+            .field("curr_in_case", &self.curr_in_case())
+            .field("primary_left", &self.primary_left())
+            .field(
+                "unknown_bits",
+                &format_args!("{:#04X}", self.unknown_bits()),
+            )
+            .finish()
+    }
 }
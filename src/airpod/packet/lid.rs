@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[repr(C, packed(1))]
 #[derive(Clone, Copy)]
 pub struct Lid {
@@ -5,10 +7,38 @@ pub struct Lid {
 }
 
 impl Lid {
+    /// Counter bumped on every lid open and close, wrapping at 8.
     pub fn switch_count(&self) -> u8 {
         self.bits & 0b0000_0111
     }
     pub fn closed(&self) -> bool {
         self.bits & 0b0000_1000 != 0
     }
+    pub fn unknown_bits(&self) -> u8 {
+        self.bits & 0b1111_0000
+    }
+
+    pub fn set_switch_count(&mut self, val: u8) {
+        self.bits = (self.bits & !0b0000_0111) | (val & 0b0000_0111);
+    }
+    pub fn set_closed(&mut self, val: bool) {
+        if val {
+            self.bits |= 0b0000_1000;
+        } else {
+            self.bits &= !0b0000_1000;
+        }
+    }
+}
+
+impl fmt::Debug for Lid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lid")
+            .field("closed", &self.closed())
+            .field("switch_count", &self.switch_count())
+            .field(
+                "unknown_bits",
+                &format_args!("{:#04X}", self.unknown_bits()),
+            )
+            .finish()
+    }
 }
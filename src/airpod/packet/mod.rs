@@ -1,13 +1,19 @@
 pub mod battery;
 pub mod color;
+pub mod connection;
 pub mod flags;
 pub mod header;
 pub mod lid;
 pub mod packet_type;
+pub mod status;
+pub mod tlv;
 
 pub use battery::Battery;
 pub use color::Color;
+pub use connection::{Connection, ConnectionState};
 pub use flags::Flags;
 pub use header::Header;
 pub use lid::Lid;
 pub use packet_type::PacketType;
+pub use status::Status;
+pub use tlv::{Tlv, Tlvs};
@@ -0,0 +1,30 @@
+use std::fmt;
+
+/// First byte of the proximity pairing body.
+#[repr(C, packed(1))]
+#[derive(Clone, Copy)]
+pub struct Status {
+    pub bits: u8,
+}
+
+impl Status {
+    /// Cleared while the case button is held to pair with a new device.
+    pub fn paired(&self) -> bool {
+        self.bits & 0b0000_0001 != 0
+    }
+    pub fn unknown_bits(&self) -> u8 {
+        self.bits & !0b0000_0001
+    }
+}
+
+impl fmt::Debug for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Status")
+            .field("paired", &self.paired())
+            .field(
+                "unknown_bits",
+                &format_args!("{:#04X}", self.unknown_bits()),
+            )
+            .finish()
+    }
+}
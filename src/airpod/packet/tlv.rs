@@ -0,0 +1,112 @@
+use crate::airpod::{DecodeError, packet::PacketType};
+
+/// One type-length-value entry of an Apple manufacturer data blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlv<'a> {
+    pub kind: u8,
+    pub value: &'a [u8],
+}
+
+impl Tlv<'_> {
+    pub fn packet_type(&self) -> PacketType {
+        PacketType::from(self.kind)
+    }
+}
+
+/// Walks the TLVs packed into a company 76 manufacturer data blob.
+#[derive(Debug, Clone)]
+pub struct Tlvs<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Tlvs<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Tlvs { data }
+    }
+}
+
+impl<'a> Iterator for Tlvs<'a> {
+    type Item = Result<Tlv<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.data {
+            [] => None,
+            [_] => {
+                self.data = &[];
+                Some(Err(DecodeError::InvalidLength {
+                    expected: 2,
+                    actual: 1,
+                }))
+            }
+            [kind, len, rest @ ..] => {
+                let len = *len as usize;
+                if rest.len() < len {
+                    self.data = &[];
+                    return Some(Err(DecodeError::InvalidRemainingLength {
+                        declared: len as u8,
+                        available: rest.len(),
+                    }));
+                }
+                let (value, rest) = rest.split_at(len);
+                self.data = rest;
+                Some(Ok(Tlv { kind: *kind, value }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_every_entry() {
+        let data = [0x10, 0x02, 0x01, 0x02, 0x0D, 0x00, 0x07, 0x01, 0x03];
+        let tlvs: Vec<_> = Tlvs::new(&data).collect();
+        assert_eq!(
+            tlvs,
+            [
+                Ok(Tlv {
+                    kind: 0x10,
+                    value: &[0x01, 0x02]
+                }),
+                Ok(Tlv {
+                    kind: 0x0D,
+                    value: &[]
+                }),
+                Ok(Tlv {
+                    kind: 0x07,
+                    value: &[0x03]
+                }),
+            ]
+        );
+        assert_eq!(tlvs[2].unwrap().packet_type(), PacketType::ProximityPairing);
+    }
+
+    #[test]
+    fn stops_at_a_broken_length() {
+        let mut tlvs = Tlvs::new(&[0x10, 0x01, 0x01, 0x07, 0x19, 0x01]);
+        assert!(matches!(tlvs.next(), Some(Ok(Tlv { kind: 0x10, .. }))));
+        assert_eq!(
+            tlvs.next(),
+            Some(Err(DecodeError::InvalidRemainingLength {
+                declared: 0x19,
+                available: 1
+            }))
+        );
+        assert_eq!(tlvs.next(), None);
+    }
+
+    #[test]
+    fn stops_at_a_lone_type_byte() {
+        let mut tlvs = Tlvs::new(&[0x07]);
+        assert_eq!(
+            tlvs.next(),
+            Some(Err(DecodeError::InvalidLength {
+                expected: 2,
+                actual: 1
+            }))
+        );
+        assert_eq!(tlvs.next(), None);
+    }
+}
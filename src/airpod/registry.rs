@@ -0,0 +1,322 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::OnceLock,
+};
+
+use anyhow::{Context, bail};
+use serde::Deserialize;
+
+use crate::airpod::{Capabilities, Family, Model, packet::Color};
+
+/// Everything known about one model id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelInfo {
+    pub id: u16,
+    pub name: String,
+    /// `Model::Unknown` for ids not compiled into `Model`.
+    pub model: Model,
+    pub family: Option<Family>,
+    pub capabilities: Capabilities,
+    pub colors: BTreeMap<u8, String>,
+}
+
+impl ModelInfo {
+    pub fn color_name(&self, color_id: u8) -> Option<&str> {
+        self.colors.get(&color_id).map(String::as_str)
+    }
+}
+
+/// Models shipped with the crate.
+const BUILTIN: &str = include_str!("models.toml");
+
+/// Model table, built from the embedded `models.toml` and optionally
+/// extended with a TOML file so new products don't need a rebuild:
+///
+/// ```toml
+/// [[model]]
+/// id = 0x2099
+/// name = "AirPods Pro 4"
+/// family = "airpods"
+/// capabilities = { anc = true, case_battery = true }
+/// colors = { "0x00" = "White" }
+/// ```
+///
+/// Entries for an id that is already known only replace the given fields.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    models: HashMap<u16, ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelsFile {
+    #[serde(default)]
+    model: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    id: u16,
+    name: Option<String>,
+    family: Option<Family>,
+    #[serde(default)]
+    capabilities: CapabilitiesEntry,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+}
+
+/// Capabilities of a models file entry, unset fields keep their value.
+#[derive(Deserialize, Default)]
+struct CapabilitiesEntry {
+    anc: Option<bool>,
+    adaptive_audio: Option<bool>,
+    conversational_awareness: Option<bool>,
+    case_battery: Option<bool>,
+    case_speaker: Option<bool>,
+    single_unit: Option<bool>,
+}
+
+impl CapabilitiesEntry {
+    fn apply(&self, capabilities: &mut Capabilities) {
+        let fields = [
+            (self.anc, &mut capabilities.anc),
+            (self.adaptive_audio, &mut capabilities.adaptive_audio),
+            (
+                self.conversational_awareness,
+                &mut capabilities.conversational_awareness,
+            ),
+            (self.case_battery, &mut capabilities.case_battery),
+            (self.case_speaker, &mut capabilities.case_speaker),
+            (self.single_unit, &mut capabilities.single_unit),
+        ];
+        for (value, field) in fields {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+    }
+}
+
+impl Registry {
+    pub fn builtin() -> Self {
+        let mut registry = Registry {
+            models: HashMap::new(),
+        };
+        registry
+            .extend_from_toml(BUILTIN)
+            .expect("embedded models.toml is valid");
+        registry
+    }
+
+    /// Built-in models, extended or overridden by the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let toml = std::fs::read_to_string(path)
+            .with_context(|| format!("reading models file {}", path.display()))?;
+        let mut registry = Self::builtin();
+        registry
+            .extend_from_toml(&toml)
+            .with_context(|| format!("parsing models file {}", path.display()))?;
+        Ok(registry)
+    }
+
+    pub fn extend_from_toml(&mut self, toml: &str) -> anyhow::Result<()> {
+        let file: ModelsFile = toml::from_str(toml)?;
+        for entry in file.model {
+            let info = self.models.entry(entry.id).or_insert_with(|| ModelInfo {
+                id: entry.id,
+                name: format!("Unknown (0x{:04X})", entry.id),
+                model: Model::from_id(entry.id),
+                family: None,
+                capabilities: Capabilities::default(),
+                colors: BTreeMap::new(),
+            });
+            if let Some(name) = entry.name {
+                info.name = name;
+            }
+            if entry.family.is_some() {
+                info.family = entry.family;
+            }
+            entry.capabilities.apply(&mut info.capabilities);
+            for (color_id, name) in entry.colors {
+                info.colors.insert(parse_u8(&color_id)?, name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, model_id: u16) -> Option<&ModelInfo> {
+        self.models.get(&model_id)
+    }
+
+    /// Display name of a model id, with the raw id for unknown ones so
+    /// users can add it to their models file.
+    pub fn name(&self, model_id: u16) -> String {
+        match self.get(model_id) {
+            Some(info) => info.name.clone(),
+            None => format!("Unknown (0x{:04X})", model_id),
+        }
+    }
+
+    pub fn color_name(&self, model_id: u16, color_id: u8) -> String {
+        match self
+            .get(model_id)
+            .and_then(|info| info.color_name(color_id))
+        {
+            Some(name) => name.to_string(),
+            None => format!("Unknown (0x{:02X})", color_id),
+        }
+    }
+
+    /// Decodes a color byte of a model id. Ids missing from the registry
+    /// use the shared palette of `Color::from`.
+    pub fn color(&self, model_id: u16, color_id: u8) -> Color {
+        match self.get(model_id) {
+            Some(info) => info
+                .color_name(color_id)
+                .map_or(Color::Unknown, Color::from_name),
+            None => Color::from(color_id),
+        }
+    }
+
+    /// Byte advertised by a model id for `color`, the inverse of `color`.
+    pub fn color_id(&self, model_id: u16, color: Color) -> Option<u8> {
+        match self.get(model_id) {
+            Some(info) => info
+                .colors
+                .iter()
+                .find(|(_, name)| name.as_str() == color.as_str())
+                .map(|(id, _)| *id),
+            None => (Color::from(color as u8) == color).then_some(color as u8),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ModelInfo> {
+        self.models.values()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+static GLOBAL: OnceLock<Registry> = OnceLock::new();
+
+/// Registry used by `AirPods::model_name`, `Model::capabilities`,
+/// `Color::for_model` and `debug_info`. Falls back to
+/// the built-in models unless `install` was called first.
+pub fn global() -> &'static Registry {
+    GLOBAL.get_or_init(Registry::builtin)
+}
+
+/// Replaces the built-in registry for the rest of the process. Fails if
+/// the global registry was already used or installed.
+pub fn install(registry: Registry) -> Result<(), Registry> {
+    GLOBAL.set(registry)
+}
+
+fn parse_u8(s: &str) -> anyhow::Result<u8> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    match parsed {
+        Ok(val) => Ok(val),
+        Err(_) => bail!("invalid color id {:?}", s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_table_covers_every_model() {
+        let registry = Registry::builtin();
+        assert_eq!(registry.iter().count(), Model::ALL.len());
+        for &model in Model::ALL {
+            let info = registry.get(model.id()).unwrap();
+            assert_eq!(info.model, model);
+            assert_eq!(info.name, model.as_str());
+            assert!(info.family.is_some(), "family of {}", model.as_str());
+            for (&color_id, name) in &info.colors {
+                let color = Color::from_name(name);
+                assert_ne!(color, Color::Unknown, "{} of {}", name, model.as_str());
+                assert_eq!(registry.color_id(model.id(), color), Some(color_id));
+            }
+        }
+
+        let max = registry.get(Model::AirPodsMax.id()).unwrap();
+        assert!(max.capabilities.single_unit && max.capabilities.anc);
+        assert!(!max.capabilities.case_battery);
+        assert_eq!(
+            registry.color(Model::AirPodsMax.id(), 0x09),
+            Color::SpaceGray
+        );
+        assert_eq!(
+            registry.color(Model::PowerbeatsPro.id(), 0x00),
+            Color::Ivory
+        );
+        assert_eq!(registry.color(Model::AirPods2.id(), 0x01), Color::Unknown);
+    }
+
+    #[test]
+    fn models_file_adds_and_overrides() {
+        let mut registry = Registry::builtin();
+        registry
+            .extend_from_toml(
+                r#"
+                [[model]]
+                id = 0x2099
+                name = "AirPods Pro 4"
+                family = "airpods"
+                capabilities = { anc = true, case_battery = true }
+                colors = { "0x00" = "White", "0x01" = "Midnight" }
+
+                [[model]]
+                id = 0x200D
+                name = "Powerbeats 4"
+                capabilities = { anc = true }
+                "#,
+            )
+            .unwrap();
+
+        let added = registry.get(0x2099).unwrap();
+        assert_eq!(added.model, Model::Unknown);
+        assert_eq!(registry.name(0x2099), "AirPods Pro 4");
+        assert_eq!(registry.color_name(0x2099, 0x00), "White");
+        assert_eq!(registry.color(0x2099, 0x00), Color::White);
+        assert_eq!(registry.color_id(0x2099, Color::White), Some(0x00));
+        // Names that are not a `Color` still have a name
+        assert_eq!(registry.color(0x2099, 0x01), Color::Unknown);
+        assert_eq!(registry.color_name(0x2099, 0x01), "Midnight");
+        assert!(added.capabilities.anc && !added.capabilities.single_unit);
+
+        let builtin = Registry::builtin();
+        let builtin = builtin.get(0x200D).unwrap();
+        let overridden = registry.get(0x200D).unwrap();
+        assert_eq!(overridden.name, "Powerbeats 4");
+        assert_eq!(overridden.model, Model::Powerbeats4);
+        assert_eq!(overridden.colors, builtin.colors);
+        // Only the given capability is replaced
+        assert!(overridden.capabilities.anc);
+        assert!(overridden.capabilities.single_unit);
+        assert_eq!(
+            Capabilities {
+                anc: false,
+                ..overridden.capabilities
+            },
+            builtin.capabilities
+        );
+    }
+
+    #[test]
+    fn unknown_ids_report_the_raw_id() {
+        let registry = Registry::builtin();
+        assert_eq!(registry.name(0x2098), "Unknown (0x2098)");
+        assert_eq!(registry.color_name(0x2098, 0x03), "Unknown (0x03)");
+        assert_eq!(registry.color(0x2098, 0x03), Color::Blue);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::airpod::{
+    AirPods, BatteryStatus, Side,
+    packet::{Battery, Color, Connection, Flags, Lid, PacketType, Status},
+};
+
+/// High-level view of a proximity pairing message, used to build
+/// advertisement bytes without touching the bitfields by hand.
+///
+/// In-ear flags only survive a round trip for buds that are not charging,
+/// since `AirPods::is_left_in_ear` and `is_right_in_ear` report a charging
+/// bud as out of the ear. Colors the model doesn't come in are encoded
+/// as 0xFF and decode as `Color::Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirPodsState {
+    pub model_id: u16,
+    pub color: Color,
+    pub broadcast_side: Side,
+    pub left: BatteryStatus,
+    pub right: BatteryStatus,
+    pub case: BatteryStatus,
+    pub left_in_ear: bool,
+    pub right_in_ear: bool,
+    pub both_in_case: bool,
+    pub lid_opened: bool,
+    pub lid_switch_count: u8,
+}
+
+impl AirPodsState {
+    pub fn to_airpods(&self) -> AirPods {
+        let (curr, anot, curr_in_ear, anot_in_ear) = match self.broadcast_side {
+            Side::Left => (self.left, self.right, self.left_in_ear, self.right_in_ear),
+            Side::Right => (self.right, self.left, self.right_in_ear, self.left_in_ear),
+        };
+
+        let mut flags = Flags { bits: 0 };
+        flags.set_curr_in_ear(curr_in_ear);
+        flags.set_anot_in_ear(anot_in_ear);
+        flags.set_both_in_case(self.both_in_case);
+        flags.set_broadcast_from(self.broadcast_side == Side::Left);
+
+        let mut battery = Battery { bits: 0, extra: 0 };
+        battery.set_curr(curr.to_nibble());
+        battery.set_anot(anot.to_nibble());
+        battery.set_case_box(self.case.to_nibble());
+        battery.set_curr_charging(curr.charging);
+        battery.set_anot_charging(anot.charging);
+        battery.set_case_charging(self.case.charging);
+
+        let mut lid = Lid { bits: 0 };
+        lid.set_switch_count(self.lid_switch_count);
+        lid.set_closed(!self.lid_opened);
+
+        AirPods {
+            packet_type: PacketType::ProximityPairing,
+            remaining_length: (AirPods::LEN - 2) as u8,
+            status: Status { bits: 0x01 },
+            model_id: self.model_id,
+            flags,
+            battery,
+            lid,
+            color_id: self.color.id_for(self.model_id).unwrap_or(0xFF),
+            connection: Connection { bits: 0x00 },
+            unk12: [0u8; 16],
+            trailing: Vec::new(),
+        }
+    }
+
+    /// Encodes the state as company 76 manufacturer data.
+    pub fn encode(&self) -> Vec<u8> {
+        self.to_airpods()
+            .to_bytes()
+            .expect("a state has no trailing bytes")
+    }
+}
+
+impl From<&AirPods> for AirPodsState {
+    fn from(ap: &AirPods) -> Self {
+        AirPodsState {
+            model_id: ap.model_id,
+            color: ap.color(),
+            broadcast_side: ap.broadcasting_side(),
+            left: ap.left_battery(),
+            right: ap.right_battery(),
+            case: ap.case_battery(),
+            left_in_ear: ap.is_left_in_ear(),
+            right_in_ear: ap.is_right_in_ear(),
+            both_in_case: ap.is_both_in_case(),
+            lid_opened: ap.is_lid_opened(),
+            lid_switch_count: ap.lid.switch_count(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl AirPodsState {
+    /// AirPods Pro 2 worn in both ears with the case closed, for tests.
+    pub(crate) fn sample() -> Self {
+        use crate::airpod::{BatteryLevel, Model};
+
+        let battery = |percent| BatteryStatus {
+            level: BatteryLevel::Percent(percent),
+            charging: false,
+        };
+        AirPodsState {
+            model_id: Model::AirPodsPro2.id(),
+            color: Color::White,
+            broadcast_side: Side::Left,
+            left: battery(80),
+            right: battery(90),
+            case: battery(50),
+            left_in_ear: true,
+            right_in_ear: true,
+            both_in_case: false,
+            lid_opened: false,
+            lid_switch_count: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airpod::{BatteryLevel, Model, as_airpods};
+
+    fn battery(level: BatteryLevel, charging: bool) -> BatteryStatus {
+        BatteryStatus { level, charging }
+    }
+
+    fn state(model: Model) -> AirPodsState {
+        AirPodsState {
+            model_id: model.id(),
+            ..AirPodsState::sample()
+        }
+    }
+
+    fn assert_round_trip(state: AirPodsState) {
+        let ap = as_airpods(&state.encode()).unwrap();
+        assert_eq!(AirPodsState::from(&ap), state);
+    }
+
+    #[test]
+    fn round_trips_models_and_sides() {
+        for &(model, color) in &[
+            (Model::AirPods2, Color::White),
+            (Model::AirPodsPro2, Color::White),
+            (Model::AirPodsMax, Color::SpaceGray),
+            (Model::PowerbeatsPro, Color::Ivory),
+            (Model::BeatsStudioBuds, Color::Navy),
+            (Model::BeatsFitPro, Color::Coral),
+        ] {
+            for side in [Side::Left, Side::Right] {
+                assert_round_trip(AirPodsState {
+                    color,
+                    broadcast_side: side,
+                    left_in_ear: false,
+                    ..state(model)
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_charging_and_disconnected_batteries() {
+        assert_round_trip(AirPodsState {
+            left: battery(BatteryLevel::Percent(100), true),
+            right: battery(BatteryLevel::Disconnected, false),
+            case: battery(BatteryLevel::Percent(0), true),
+            // A charging bud always reads as out of the ear
+            left_in_ear: false,
+            ..state(Model::AirPodsPro)
+        });
+        assert_round_trip(AirPodsState {
+            broadcast_side: Side::Right,
+            left: battery(BatteryLevel::Disconnected, false),
+            right: battery(BatteryLevel::Percent(30), true),
+            case: battery(BatteryLevel::Disconnected, false),
+            right_in_ear: false,
+            ..state(Model::AirPods3)
+        });
+    }
+
+    #[test]
+    fn round_trips_lid_states() {
+        for lid_switch_count in 0..8 {
+            for lid_opened in [false, true] {
+                assert_round_trip(AirPodsState {
+                    left_in_ear: false,
+                    right_in_ear: false,
+                    both_in_case: true,
+                    lid_opened,
+                    lid_switch_count,
+                    ..state(Model::AirPods4Anc)
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::airpod::{AirPods, BatteryStatus};
+
+/// Proximity pairing state shaped after the kind of device that sent it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceView {
+    /// Two buds and a charging case.
+    Earbuds {
+        left: BatteryStatus,
+        right: BatteryStatus,
+        case: BatteryStatus,
+        left_in_ear: bool,
+        right_in_ear: bool,
+        both_in_case: bool,
+        lid_opened: bool,
+    },
+    /// One unit with a single battery and no case, such as the AirPods Max
+    /// or Beats over-ears.
+    SingleUnit {
+        battery: BatteryStatus,
+        on_head: bool,
+    },
+}
+
+impl DeviceView {
+    pub fn new(ap: &AirPods) -> Self {
+        if ap.capabilities().single_unit {
+            let battery = BatteryStatus::from_nibble(ap.battery.curr(), ap.battery.curr_charging());
+            DeviceView::SingleUnit {
+                battery,
+                on_head: !battery.charging && ap.flags.curr_in_ear(),
+            }
+        } else {
+            DeviceView::Earbuds {
+                left: ap.left_battery(),
+                right: ap.right_battery(),
+                case: ap.case_battery(),
+                left_in_ear: ap.is_left_in_ear(),
+                right_in_ear: ap.is_right_in_ear(),
+                both_in_case: ap.is_both_in_case(),
+                lid_opened: ap.is_lid_opened(),
+            }
+        }
+    }
+}
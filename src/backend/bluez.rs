@@ -0,0 +1,444 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::Poll,
+    thread::{self, JoinHandle},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use futures_core::Stream;
+use tokio::sync::oneshot;
+use zbus::{
+    MatchRule,
+    blocking::{Connection, MessageIterator, Proxy},
+    message::Type,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+};
+
+use crate::{
+    source::{AddressType, Advertisement, AdvertisementSource, Sink},
+    tracker::PairedDevice,
+};
+
+const BLUEZ: &str = "org.bluez";
+const DEVICE: &str = "org.bluez.Device1";
+
+type Properties = HashMap<String, OwnedValue>;
+
+/// Advertisements reported by BlueZ over D-Bus: discovery is started on
+/// `org.bluez.Adapter1` and every `Device1` update carrying manufacturer
+/// data becomes one advertisement.
+pub struct BluezSource {
+    connection: Connection,
+    service: String,
+    adapter: OwnedObjectPath,
+    sink: Arc<Mutex<Option<Sink>>>,
+    discovering: bool,
+    /// Thread reading the signals of a running scan, and what wakes it up
+    /// to stop.
+    signals: Option<(oneshot::Sender<()>, JoinHandle<()>)>,
+}
+
+impl BluezSource {
+    /// Scans on the first adapter of the system bus.
+    pub fn system() -> anyhow::Result<Self> {
+        let connection = Connection::system().context("connecting to the system bus")?;
+        Self::new(connection, BLUEZ, "/org/bluez/hci0")
+    }
+
+    /// Scans on `adapter` of the BlueZ service named `service`, which can be
+    /// a stand-in on a private bus.
+    pub fn new(connection: Connection, service: &str, adapter: &str) -> anyhow::Result<Self> {
+        Ok(BluezSource {
+            connection,
+            service: service.to_string(),
+            adapter: OwnedObjectPath::try_from(adapter)?,
+            sink: Arc::new(Mutex::new(None)),
+            discovering: false,
+            signals: None,
+        })
+    }
+
+    /// Lists the devices paired with this computer.
+    pub fn paired_devices(&self) -> anyhow::Result<Vec<PairedDevice>> {
+        let mut paired = Vec::new();
+        for properties in self.managed_devices()?.into_values() {
+            let get = |name: &str| properties.get(name).and_then(|v| v.try_clone().ok());
+            if !get("Paired").is_some_and(|v| bool::try_from(v).unwrap_or(false)) {
+                continue;
+            }
+            let Some(address) = get("Address")
+                .and_then(|v| String::try_from(v).ok())
+                .and_then(|address| parse_address(&address))
+            else {
+                continue;
+            };
+            let name = get("Alias")
+                .or_else(|| get("Name"))
+                .and_then(|v| String::try_from(v).ok())
+                .unwrap_or_default();
+            paired.push(PairedDevice::new(name).with_address(address));
+        }
+        Ok(paired)
+    }
+
+    /// `Device1` properties of every device BlueZ knows about.
+    fn managed_devices(&self) -> anyhow::Result<HashMap<OwnedObjectPath, Properties>> {
+        let manager = Proxy::new(
+            &self.connection,
+            self.service.as_str(),
+            "/",
+            "org.freedesktop.DBus.ObjectManager",
+        )?;
+        let objects: HashMap<OwnedObjectPath, HashMap<String, Properties>> =
+            manager.call("GetManagedObjects", &())?;
+        let devices = objects
+            .into_iter()
+            .filter(|(path, _)| is_under(path, &self.adapter))
+            .filter_map(|(path, mut interfaces)| Some((path, interfaces.remove(DEVICE)?)))
+            .collect();
+        Ok(devices)
+    }
+
+    fn adapter(&self) -> zbus::Result<Proxy<'static>> {
+        Proxy::new(
+            &self.connection,
+            self.service.clone(),
+            self.adapter.clone(),
+            "org.bluez.Adapter1",
+        )
+    }
+}
+
+impl AdvertisementSource for BluezSource {
+    fn start(&mut self, sink: Sink) -> anyhow::Result<()> {
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .sender(self.service.as_str())?
+            .build();
+        // Subscribed before discovery starts so no signal is missed
+        let mut signals =
+            MessageIterator::for_match_rule(rule, &self.connection, None)?.into_inner();
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+
+        // Devices BlueZ already knows about only report what changed
+        let mut devices = self.managed_devices()?;
+
+        // Without duplicate data BlueZ only reports manufacturer data
+        // when it differs from the previous advert
+        let adapter = self.adapter()?;
+        let filter = HashMap::from([
+            ("Transport", Value::from("le")),
+            ("DuplicateData", Value::from(true)),
+        ]);
+        adapter
+            .call_method("SetDiscoveryFilter", &(filter,))
+            .context("setting the discovery filter")?;
+        adapter
+            .call_method("StartDiscovery", &())
+            .context("starting discovery")?;
+        self.discovering = true;
+
+        *self.sink.lock().unwrap() = Some(sink);
+        let sink = self.sink.clone();
+        let adapter = self.adapter.clone();
+        let (stop, mut stopped) = oneshot::channel();
+        let thread = thread::spawn(move || {
+            loop {
+                // Delivered outside of the runtime, sinks may block
+                let message = runtime.block_on(std::future::poll_fn(|cx| {
+                    if Pin::new(&mut stopped).poll(cx).is_ready() {
+                        return Poll::Ready(None);
+                    }
+                    Pin::new(&mut signals).poll_next(cx)
+                }));
+                let Some(Ok(message)) = message else {
+                    break;
+                };
+                let Some(advert) = handle_signal(&message, &adapter, &mut devices) else {
+                    continue;
+                };
+                let mut sink = sink.lock().unwrap();
+                let keep_going = sink.as_mut().is_some_and(|deliver| deliver(advert));
+                if !keep_going {
+                    sink.take();
+                    break;
+                }
+            }
+            // Dropping the stream removes the match rule
+            drop(signals);
+        });
+        self.signals = Some((stop, thread));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        self.sink.lock().unwrap().take();
+        if let Some((stop, thread)) = self.signals.take() {
+            let _ = stop.send(());
+            let _ = thread.join();
+        }
+        if self.discovering {
+            self.discovering = false;
+            self.adapter()?.call_method("StopDiscovery", &())?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for BluezSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Whether `path` is an object below `adapter`, so `/org/bluez/hci0` does
+/// not take in the devices of `/org/bluez/hci01`.
+fn is_under(path: &OwnedObjectPath, adapter: &OwnedObjectPath) -> bool {
+    path.as_str()
+        .strip_prefix(adapter.as_str())
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Updates the known devices from an `InterfacesAdded` or
+/// `PropertiesChanged` signal and returns the advertisement it carried.
+fn handle_signal(
+    message: &zbus::Message,
+    adapter: &OwnedObjectPath,
+    devices: &mut HashMap<OwnedObjectPath, Properties>,
+) -> Option<Advertisement> {
+    let header = message.header();
+    let (path, changed) = match header.member()?.as_str() {
+        "InterfacesAdded" => {
+            let (path, mut interfaces): (OwnedObjectPath, HashMap<String, Properties>) =
+                message.body().deserialize().ok()?;
+            (path, interfaces.remove(DEVICE)?)
+        }
+        "PropertiesChanged" => {
+            let (interface, changed, _): (String, Properties, Vec<String>) =
+                message.body().deserialize().ok()?;
+            if interface != DEVICE {
+                return None;
+            }
+            (OwnedObjectPath::from(header.path()?.to_owned()), changed)
+        }
+        _ => return None,
+    };
+    if !is_under(&path, adapter) {
+        return None;
+    }
+
+    let carries_advert = changed.contains_key("ManufacturerData");
+    let properties = devices.entry(path).or_default();
+    properties.extend(changed);
+    if !carries_advert {
+        return None;
+    }
+    to_advertisement(properties)
+}
+
+fn to_advertisement(properties: &Properties) -> Option<Advertisement> {
+    let get = |name: &str| properties.get(name).and_then(|v| v.try_clone().ok());
+
+    let address = parse_address(&String::try_from(get("Address")?).ok()?)?;
+    let address_type = match get("AddressType").and_then(|v| String::try_from(v).ok()) {
+        Some(kind) if kind == "public" => AddressType::Public,
+        Some(kind) if kind == "random" => AddressType::Random,
+        _ => AddressType::Unknown,
+    };
+    let entries: HashMap<u16, OwnedValue> = get("ManufacturerData")?.try_into().ok()?;
+    let mut manufacturer_data: Vec<(u16, Vec<u8>)> = entries
+        .into_iter()
+        .filter_map(|(company_id, data)| Some((company_id, Vec::try_from(data).ok()?)))
+        .collect();
+    manufacturer_data.sort();
+
+    Some(Advertisement {
+        address,
+        address_type,
+        rssi: get("RSSI").and_then(|v| i16::try_from(v).ok()),
+        tx_power: get("TxPower").and_then(|v| i16::try_from(v).ok()),
+        at: SystemTime::now(),
+        manufacturer_data,
+    })
+}
+
+/// Parses "AA:BB:CC:DD:EE:FF", most significant byte first.
+fn parse_address(s: &str) -> Option<u64> {
+    let mut address = 0u64;
+    let mut count = 0;
+    for part in s.split(':') {
+        address = (address << 8) | u64::from(u8::from_str_radix(part, 16).ok()?);
+        count += 1;
+    }
+    (count == 6).then_some(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+        sync::mpsc,
+        time::Duration,
+    };
+
+    use zbus::{blocking::connection, fdo::ObjectManager, interface};
+
+    use super::*;
+    use crate::airpod::as_airpods;
+
+    const ADAPTER: &str = "/org/bluez/hci0";
+    const SERVICE: &str = "org.bluez.Mock";
+    const PAYLOAD: [u8; 27] = [
+        0x07, 0x19, 0x01, 0x14, 0x20, 0x2B, 0x98, 0x8F, 0x01, 0x00, 0x05, 0, 1, 2, 3, 4, 5, 6, 7,
+        8, 9, 10, 11, 12, 13, 14, 15,
+    ];
+
+    /// Private bus daemon, killed on drop.
+    struct Bus(Child);
+
+    impl Bus {
+        fn start() -> (Self, String) {
+            let mut child = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("dbus-daemon must be installed to run the BlueZ tests");
+            let mut address = String::new();
+            BufReader::new(child.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            (Bus(child), address.trim().to_string())
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[derive(Default)]
+    struct Adapter {
+        discovering: bool,
+        filter: HashMap<String, OwnedValue>,
+    }
+
+    #[interface(name = "org.bluez.Adapter1")]
+    impl Adapter {
+        fn start_discovery(&mut self) {
+            self.discovering = true;
+        }
+
+        fn stop_discovery(&mut self) {
+            self.discovering = false;
+        }
+
+        fn set_discovery_filter(&mut self, filter: HashMap<String, OwnedValue>) {
+            self.filter = filter;
+        }
+    }
+
+    struct Device {
+        address: &'static str,
+        rssi: i16,
+        payload: Vec<u8>,
+    }
+
+    #[interface(name = "org.bluez.Device1")]
+    impl Device {
+        #[zbus(property)]
+        fn address(&self) -> String {
+            self.address.to_string()
+        }
+
+        #[zbus(property)]
+        fn address_type(&self) -> String {
+            "random".to_string()
+        }
+
+        #[zbus(property, name = "RSSI")]
+        fn rssi(&self) -> i16 {
+            self.rssi
+        }
+
+        #[zbus(property)]
+        fn manufacturer_data(&self) -> HashMap<u16, Value<'_>> {
+            HashMap::from([(0x004C, Value::from(self.payload.clone()))])
+        }
+    }
+
+    #[test]
+    fn reads_adverts_from_a_mock_bluez() {
+        let (_bus, address) = Bus::start();
+        let known = Device {
+            address: "4A:11:22:33:44:55",
+            rssi: -40,
+            payload: PAYLOAD.to_vec(),
+        };
+        let server = connection::Builder::address(address.as_str())
+            .unwrap()
+            .name(SERVICE)
+            .unwrap()
+            .serve_at("/", ObjectManager)
+            .unwrap()
+            .serve_at(ADAPTER, Adapter::default())
+            .unwrap()
+            .serve_at("/org/bluez/hci0/dev_known", known)
+            .unwrap()
+            .build()
+            .unwrap();
+        let client = connection::Builder::address(address.as_str())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut source = BluezSource::new(client, SERVICE, ADAPTER).unwrap();
+        let (tx, rx) = mpsc::channel();
+        source
+            .start(Box::new(move |advert| tx.send(advert).is_ok()))
+            .unwrap();
+
+        let objects = server.object_server();
+        let adapter = objects.interface::<_, Adapter>(ADAPTER).unwrap();
+        assert!(adapter.get().discovering);
+        let transport = adapter.get().filter["Transport"].try_clone().unwrap();
+        assert_eq!(String::try_from(transport).unwrap(), "le");
+
+        // A device BlueZ already knew about only reports what changed
+        let known = objects
+            .interface::<_, Device>("/org/bluez/hci0/dev_known")
+            .unwrap();
+        // Lid closed
+        known.get_mut().payload[8] = 0x0A;
+        let emitter = known.signal_emitter().clone();
+        zbus::block_on(known.get().manufacturer_data_changed(&emitter)).unwrap();
+
+        let advert = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(advert.address, 0x4A11_2233_4455);
+        assert_eq!(advert.address_type, AddressType::Random);
+        assert_eq!(advert.rssi, Some(-40));
+        let ap = as_airpods(advert.apple_data().unwrap()).unwrap();
+        assert!(ap.lid.closed());
+
+        // A new device comes with all its properties
+        let added = Device {
+            address: "5B:00:00:00:00:01",
+            rssi: -70,
+            payload: PAYLOAD.to_vec(),
+        };
+        objects.at("/org/bluez/hci0/dev_added", added).unwrap();
+        let advert = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(advert.address, 0x5B00_0000_0001);
+        assert_eq!(advert.rssi, Some(-70));
+
+        source.stop().unwrap();
+        assert!(!adapter.get().discovering);
+    }
+}
@@ -0,0 +1,6 @@
+//! Platform integrations that feed advertisements into the parser.
+
+#[cfg(all(target_os = "linux", feature = "bluez"))]
+pub mod bluez;
+#[cfg(all(windows, feature = "windows"))]
+pub mod windows;
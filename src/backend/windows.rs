@@ -0,0 +1,140 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use windows::{
+    Devices::{
+        Bluetooth::{
+            Advertisement::{
+                BluetoothLEAdvertisementReceivedEventArgs, BluetoothLEAdvertisementWatcher,
+            },
+            BluetoothAddressType, BluetoothDevice,
+        },
+        Enumeration::DeviceInformation,
+    },
+    Foundation::{DateTime, TypedEventHandler},
+    Storage::Streams::{DataReader, IBuffer},
+    core::{Ref, Result},
+};
+
+use crate::{
+    source::{AddressType, Advertisement, AdvertisementSource, Sink},
+    tracker::PairedDevice,
+};
+
+/// Converts a Windows IBuffer to a Vec<u8>
+pub fn buffer_to_vec(buffer: &IBuffer) -> Result<Vec<u8>> {
+    let reader = DataReader::FromBuffer(buffer)?;
+    let mut bytes = vec![0u8; reader.UnconsumedBufferLength()? as usize];
+    reader.ReadBytes(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Lists the Bluetooth devices paired with this computer
+pub fn paired_devices() -> Result<Vec<PairedDevice>> {
+    let selector = BluetoothDevice::GetDeviceSelectorFromPairingState(true)?;
+    let infos = DeviceInformation::FindAllAsyncAqsFilter(&selector)?.join()?;
+
+    let mut paired = Vec::new();
+    for i in 0..infos.Size()? {
+        let info = infos.GetAt(i)?;
+        let device = BluetoothDevice::FromIdAsync(&info.Id()?)?.join()?;
+        paired.push(
+            PairedDevice::new(device.Name()?.to_string_lossy())
+                .with_address(device.BluetoothAddress()?),
+        );
+    }
+    Ok(paired)
+}
+
+/// Advertisements received by the WinRT `BluetoothLEAdvertisementWatcher`.
+pub struct WinRtSource {
+    watcher: BluetoothLEAdvertisementWatcher,
+    token: Option<i64>,
+}
+
+impl WinRtSource {
+    pub fn new() -> Result<Self> {
+        Ok(WinRtSource {
+            watcher: BluetoothLEAdvertisementWatcher::new()?,
+            token: None,
+        })
+    }
+}
+
+impl AdvertisementSource for WinRtSource {
+    fn start(&mut self, sink: Sink) -> anyhow::Result<()> {
+        let sink = Arc::new(Mutex::new(Some(sink)));
+        let watcher = self.watcher.clone();
+        let token = self.watcher.Received(&TypedEventHandler::new(
+            move |_: Ref<BluetoothLEAdvertisementWatcher>,
+                  args: Ref<BluetoothLEAdvertisementReceivedEventArgs>| {
+                if let Some(args) = &*args {
+                    let advert = to_advertisement(args)?;
+                    let mut sink = sink.lock().unwrap();
+                    let keep_going = sink.as_mut().is_some_and(|sink| sink(advert));
+                    if !keep_going && sink.take().is_some() {
+                        watcher.Stop()?;
+                    }
+                }
+                Ok(())
+            },
+        ))?;
+        self.token = Some(token);
+        self.watcher.Start()?;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        self.watcher.Stop()?;
+        if let Some(token) = self.token.take() {
+            self.watcher.RemoveReceived(token)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WinRtSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn to_advertisement(args: &BluetoothLEAdvertisementReceivedEventArgs) -> Result<Advertisement> {
+    let entries = args.Advertisement()?.ManufacturerData()?;
+    let mut manufacturer_data = Vec::new();
+    for i in 0..entries.Size()? {
+        let entry = entries.GetAt(i)?;
+        manufacturer_data.push((entry.CompanyId()?, buffer_to_vec(&entry.Data()?)?));
+    }
+
+    let address_type = match args.BluetoothAddressType() {
+        Ok(BluetoothAddressType::Public) => AddressType::Public,
+        Ok(BluetoothAddressType::Random) => AddressType::Random,
+        _ => AddressType::Unknown,
+    };
+    Ok(Advertisement {
+        address: args.BluetoothAddress()?,
+        address_type,
+        rssi: Some(args.RawSignalStrengthInDBm()?),
+        tx_power: args
+            .TransmitPowerLevelInDBm()
+            .and_then(|power| power.Value())
+            .ok(),
+        at: to_system_time(args.Timestamp()?),
+        manufacturer_data,
+    })
+}
+
+/// `DateTime` counts 100ns ticks since 1601-01-01.
+fn to_system_time(time: DateTime) -> SystemTime {
+    const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+    let ticks = time.UniversalTime - UNIX_EPOCH_TICKS;
+    let since_epoch = Duration::from_nanos(ticks.unsigned_abs() * 100);
+    if ticks >= 0 {
+        SystemTime::UNIX_EPOCH + since_epoch
+    } else {
+        SystemTime::UNIX_EPOCH - since_epoch
+    }
+}
@@ -0,0 +1,8 @@
+//! Parsing and tracking of Apple proximity pairing and Continuity
+//! advertisements. Everything but `backend` is platform-neutral.
+
+pub mod airpod;
+pub mod backend;
+pub mod scan;
+pub mod source;
+pub mod tracker;
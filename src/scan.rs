@@ -0,0 +1,435 @@
+//! Async stream of device updates on top of any `AdvertisementSource`.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures_core::Stream;
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{Interval, MissedTickBehavior},
+};
+
+use crate::{
+    source::{Advertisement, AdvertisementSource},
+    tracker::{ChangeTracker, Device, DeviceKey, DeviceTable, Event, OwnershipResolver},
+};
+
+#[derive(Clone)]
+pub struct ScanOptions {
+    /// Advertisements buffered ahead of the consumer. Once full, the source
+    /// is held up until the stream is polled again.
+    pub buffer: usize,
+    /// Silence after which a device is reported lost.
+    pub lost_after: Duration,
+    /// Devices paired with this computer, see `DeviceTable::with_owners`.
+    pub owners: OwnershipResolver,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            buffer: 64,
+            lost_after: ChangeTracker::DEFAULT_LOST_AFTER,
+            owners: OwnershipResolver::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum DeviceUpdate {
+    /// A device advertised; `events` is what changed, possibly nothing.
+    Seen {
+        device: Box<Device>,
+        events: Vec<Event>,
+    },
+    /// A device went silent for longer than `ScanOptions::lost_after`.
+    Lost { key: DeviceKey, event: Event },
+}
+
+impl DeviceUpdate {
+    pub fn key(&self) -> &DeviceKey {
+        match self {
+            DeviceUpdate::Seen { device, .. } => &device.key,
+            DeviceUpdate::Lost { key, .. } => key,
+        }
+    }
+}
+
+/// Stream returned by `scan`. Dropping it stops the source.
+pub struct Scan<S: AdvertisementSource> {
+    source: Arc<Mutex<S>>,
+    adverts: mpsc::Receiver<Advertisement>,
+    /// Error of `AdvertisementSource::start`, if it failed.
+    failed: Option<oneshot::Receiver<anyhow::Error>>,
+    table: DeviceTable,
+    pending: VecDeque<DeviceUpdate>,
+    expiry: Option<Interval>,
+    /// Timestamp of the latest advert and when it was received, so lost
+    /// devices are judged on the source's clock, which is in the past for
+    /// replays.
+    clock: Option<(SystemTime, Instant)>,
+    stopped: bool,
+}
+
+/// Starts `source` and turns its advertisements into device updates. If
+/// the source fails to start, the error is yielded and the stream ends.
+pub fn scan<S>(source: S, options: ScanOptions) -> Scan<S>
+where
+    S: AdvertisementSource + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(options.buffer.max(1));
+    let (failed_tx, failed_rx) = oneshot::channel();
+    let source = Arc::new(Mutex::new(source));
+
+    // Sources may deliver from within `start`, where blocking on a full
+    // channel must not hold up the async runtime.
+    let starting = source.clone();
+    thread::spawn(move || {
+        let mut source = starting.lock().unwrap();
+        // Dropped before it got going
+        if tx.is_closed() {
+            return;
+        }
+        // Held until the error is sent, so the stream can't end before it
+        // sees the error
+        let open = tx.clone();
+        let sink = Box::new(move |advert| tx.blocking_send(advert).is_ok());
+        if let Err(err) = source.start(sink) {
+            let _ = failed_tx.send(err.context("starting the advertisement source"));
+        }
+        drop(open);
+    });
+
+    let table = DeviceTable::new()
+        .with_owners(options.owners)
+        .with_lost_after(options.lost_after);
+    Scan {
+        source,
+        adverts: rx,
+        failed: Some(failed_rx),
+        table,
+        pending: VecDeque::new(),
+        expiry: None,
+        clock: None,
+        stopped: false,
+    }
+}
+
+impl<S: AdvertisementSource> Scan<S> {
+    /// Waits for the next update, for use in `tokio::select!` without
+    /// pulling in `StreamExt`.
+    pub async fn next(&mut self) -> Option<anyhow::Result<DeviceUpdate>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    /// Devices seen so far and not yet lost.
+    pub fn devices(&self) -> &DeviceTable {
+        &self.table
+    }
+
+    /// Stops the source like dropping the stream does, but returns its
+    /// error, e.g. a capture that could not be written.
+    pub fn stop(mut self) -> anyhow::Result<()> {
+        self.stop_source()
+    }
+
+    fn stop_source(&mut self) -> anyhow::Result<()> {
+        if self.stopped {
+            return Ok(());
+        }
+        self.stopped = true;
+        // Closing first fails a sink blocked on a full channel, so a source
+        // delivering from within `start` lets go of the lock.
+        self.adverts.close();
+        match self.source.lock() {
+            Ok(mut source) => source.stop(),
+            Err(_) => anyhow::bail!("the advertisement source panicked"),
+        }
+    }
+
+    fn now(&self) -> SystemTime {
+        match self.clock {
+            Some((at, received)) => at + received.elapsed(),
+            None => SystemTime::now(),
+        }
+    }
+
+    /// Error of a source that failed to start, once it is known.
+    fn poll_failed(&mut self, cx: &mut Context<'_>) -> Option<anyhow::Error> {
+        let failed = self.failed.as_mut()?;
+        let result = match Pin::new(failed).poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return None,
+        };
+        self.failed = None;
+        result.ok()
+    }
+
+    fn expire(&mut self) {
+        let now = self.now();
+        for (key, event) in self.table.expire(now) {
+            self.pending.push_back(DeviceUpdate::Lost { key, event });
+        }
+    }
+
+    fn ingest(&mut self, advert: Advertisement) {
+        self.clock = Some((advert.at, Instant::now()));
+        self.expire();
+        if let Some((device, events)) = self.table.ingest(&advert) {
+            self.pending.push_back(DeviceUpdate::Seen {
+                device: Box::new(device.clone()),
+                events,
+            });
+        }
+    }
+}
+
+impl<S: AdvertisementSource> Stream for Scan<S> {
+    type Item = anyhow::Result<DeviceUpdate>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<anyhow::Result<DeviceUpdate>>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(update) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(update)));
+            }
+            if let Some(err) = this.poll_failed(cx) {
+                return Poll::Ready(Some(Err(err)));
+            }
+
+            let lost_after = this.table.lost_after();
+            let expiry = this.expiry.get_or_insert_with(|| {
+                let period = (lost_after / 4).max(Duration::from_millis(100));
+                let mut expiry = tokio::time::interval(period);
+                expiry.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                expiry
+            });
+            if expiry.poll_tick(cx).is_ready() {
+                this.expire();
+                continue;
+            }
+
+            match this.adverts.poll_recv(cx) {
+                Poll::Ready(Some(advert)) => this.ingest(advert),
+                // The source finished, e.g. the end of a replay, or failed
+                // to start
+                Poll::Ready(None) => return Poll::Ready(this.poll_failed(cx).map(Err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<S: AdvertisementSource> Drop for Scan<S> {
+    fn drop(&mut self) {
+        let _ = self.stop_source();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{
+        airpod::{AirPodsState, Side, VENDOR_ID},
+        source::{AddressType, MemorySource},
+        tracker::EventKind,
+    };
+
+    fn state(left_in_ear: bool) -> AirPodsState {
+        AirPodsState {
+            left_in_ear,
+            ..AirPodsState::sample()
+        }
+    }
+
+    fn advert(address: u64, manufacturer_data: Vec<(u16, Vec<u8>)>) -> Advertisement {
+        Advertisement {
+            address,
+            address_type: AddressType::Random,
+            rssi: Some(-60),
+            tx_power: None,
+            at: SystemTime::now(),
+            manufacturer_data,
+        }
+    }
+
+    fn airpods(address: u64, state: AirPodsState) -> Advertisement {
+        advert(address, vec![(VENDOR_ID, state.encode())])
+    }
+
+    fn kinds(update: &DeviceUpdate) -> Vec<EventKind> {
+        match update {
+            DeviceUpdate::Seen { events, .. } => events.iter().map(|event| event.kind).collect(),
+            DeviceUpdate::Lost { event, .. } => vec![event.kind],
+        }
+    }
+
+    #[tokio::test]
+    async fn turns_adverts_into_device_updates() {
+        let source = MemorySource::new(vec![
+            airpods(1, state(true)),
+            // Not AirPods, no update
+            advert(3, vec![(0x0006, vec![1, 2, 3])]),
+            airpods(2, state(true)),
+            airpods(1, state(false)),
+        ]);
+        let mut updates = scan(source, ScanOptions::default());
+
+        let mut seen = Vec::new();
+        while let Some(update) = updates.next().await {
+            seen.push(update.unwrap());
+        }
+
+        let keys: Vec<_> = seen.iter().map(|update| update.key().clone()).collect();
+        assert_eq!(
+            keys,
+            [
+                DeviceKey::Address(1),
+                DeviceKey::Address(2),
+                DeviceKey::Address(1)
+            ]
+        );
+        assert!(kinds(&seen[0]).contains(&EventKind::DeviceAppeared));
+        assert!(kinds(&seen[1]).contains(&EventKind::DeviceAppeared));
+        assert_eq!(
+            kinds(&seen[2]),
+            [EventKind::InEarChanged {
+                side: Side::Left,
+                in_ear: false
+            }]
+        );
+        assert_eq!(updates.devices().len(), 2);
+    }
+
+    /// Memory source counting the adverts it got through and whether it
+    /// was stopped.
+    #[derive(Default)]
+    struct Counting {
+        inner: MemorySource,
+        delivered: Arc<AtomicUsize>,
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl AdvertisementSource for Counting {
+        fn start(&mut self, mut sink: crate::source::Sink) -> anyhow::Result<()> {
+            let delivered = self.delivered.clone();
+            self.inner.start(Box::new(move |advert| {
+                let sent = sink(advert);
+                if sent {
+                    delivered.fetch_add(1, Ordering::SeqCst);
+                }
+                sent
+            }))
+        }
+
+        fn stop(&mut self) -> anyhow::Result<()> {
+            self.stopped.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn counting(adverts: usize) -> Counting {
+        let adverts = (0..adverts as u64)
+            .map(|i| airpods(i % 5, state(i % 2 == 0)))
+            .collect();
+        Counting {
+            inner: MemorySource::new(adverts),
+            ..Counting::default()
+        }
+    }
+
+    /// Waits for the source thread to deliver `count` adverts, then a bit
+    /// longer to catch it going past.
+    async fn settle(delivered: &AtomicUsize, count: usize) {
+        for _ in 0..100 {
+            if delivered.load(Ordering::SeqCst) >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_consumer_when_the_buffer_is_full() {
+        let source = counting(200);
+        let delivered = source.delivered.clone();
+        let options = ScanOptions {
+            buffer: 4,
+            ..ScanOptions::default()
+        };
+        let mut updates = scan(source, options);
+
+        // The source is held up once the buffer is full
+        settle(&delivered, 4).await;
+        assert_eq!(delivered.load(Ordering::SeqCst), 4);
+
+        // Taking one advert out lets exactly one more in
+        assert!(updates.next().await.unwrap().is_ok());
+        settle(&delivered, 5).await;
+        assert_eq!(delivered.load(Ordering::SeqCst), 5);
+
+        let mut count = 1;
+        while let Some(update) = updates.next().await {
+            assert!(matches!(update.unwrap(), DeviceUpdate::Seen { .. }));
+            count += 1;
+        }
+        assert_eq!(count, 200);
+        assert_eq!(delivered.load(Ordering::SeqCst), 200);
+        assert_eq!(updates.devices().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn dropping_the_stream_stops_the_source() {
+        let source = counting(200);
+        let (delivered, stopped) = (source.delivered.clone(), source.stopped.clone());
+        let options = ScanOptions {
+            buffer: 1,
+            ..ScanOptions::default()
+        };
+        let mut updates = scan(source, options);
+        assert!(updates.next().await.unwrap().is_ok());
+        settle(&delivered, 2).await;
+        assert!(!stopped.load(Ordering::SeqCst));
+
+        // The source is blocked on the full buffer when the stream goes
+        drop(updates);
+        assert!(stopped.load(Ordering::SeqCst));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(delivered.load(Ordering::SeqCst), 2);
+    }
+
+    struct Broken;
+
+    impl AdvertisementSource for Broken {
+        fn start(&mut self, _sink: crate::source::Sink) -> anyhow::Result<()> {
+            anyhow::bail!("adapter is powered off")
+        }
+
+        fn stop(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn yields_the_error_of_a_source_that_fails_to_start() {
+        let mut updates = scan(Broken, ScanOptions::default());
+        let Some(Err(err)) = updates.next().await else {
+            panic!("expected the start error");
+        };
+        assert!(format!("{:#}", err).contains("adapter is powered off"));
+        assert!(updates.next().await.is_none());
+    }
+}
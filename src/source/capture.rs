@@ -0,0 +1,383 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    airpod::{VENDOR_ID, as_airpods, packet::Tlvs},
+    source::{AddressType, Advertisement, AdvertisementSource, Sink},
+};
+
+/// One line of a capture file:
+///
+/// ```json
+/// {"at_ms":1760000000123,"address":"4A:11:22:33:44:55","address_type":"random","rssi":-52,"tx_power":null,"manufacturer_data":[[76,"07190114..."]]}
+/// ```
+#[derive(Serialize, Deserialize)]
+struct Record {
+    /// Milliseconds since the Unix epoch.
+    at_ms: u64,
+    address: String,
+    address_type: String,
+    rssi: Option<i16>,
+    tx_power: Option<i16>,
+    /// Company id and hex encoded data, in the order received.
+    manufacturer_data: Vec<(u16, String)>,
+}
+
+impl From<&Advertisement> for Record {
+    fn from(advert: &Advertisement) -> Self {
+        let at = advert.at.duration_since(SystemTime::UNIX_EPOCH);
+        Record {
+            at_ms: at.unwrap_or_default().as_millis() as u64,
+            address: format_address(advert.address),
+            address_type: match advert.address_type {
+                AddressType::Public => "public",
+                AddressType::Random => "random",
+                AddressType::Unknown => "unknown",
+            }
+            .to_string(),
+            rssi: advert.rssi,
+            tx_power: advert.tx_power,
+            manufacturer_data: advert
+                .manufacturer_data
+                .iter()
+                .map(|(company_id, data)| (*company_id, to_hex(data)))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<Record> for Advertisement {
+    type Error = anyhow::Error;
+
+    fn try_from(record: Record) -> anyhow::Result<Self> {
+        let mut manufacturer_data = Vec::new();
+        for (company_id, hex) in record.manufacturer_data {
+            manufacturer_data.push((company_id, from_hex(&hex)?));
+        }
+        Ok(Advertisement {
+            address: parse_address(&record.address)?,
+            address_type: match record.address_type.as_str() {
+                "public" => AddressType::Public,
+                "random" => AddressType::Random,
+                _ => AddressType::Unknown,
+            },
+            rssi: record.rssi,
+            tx_power: record.tx_power,
+            at: SystemTime::UNIX_EPOCH + Duration::from_millis(record.at_ms),
+            manufacturer_data,
+        })
+    }
+}
+
+/// Writes advertisements as JSON Lines, optionally scrubbed first.
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+    scrubber: Option<Scrubber>,
+}
+
+impl CaptureWriter<File> {
+    pub fn create(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("creating capture file {}", path.display()))?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(writer: W) -> Self {
+        CaptureWriter {
+            writer,
+            scrubber: None,
+        }
+    }
+
+    /// Runs every advertisement through a `Scrubber` before writing it.
+    pub fn scrubbed(mut self) -> Self {
+        self.scrubber = Some(Scrubber::new());
+        self
+    }
+
+    pub fn write(&mut self, advert: &Advertisement) -> anyhow::Result<()> {
+        let record = match &mut self.scrubber {
+            Some(scrubber) => Record::from(&scrubber.scrub(advert)),
+            None => Record::from(advert),
+        };
+        serde_json::to_writer(&mut self.writer, &record)?;
+        // One flushed line per advert, so a capture cut short stays readable
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes every advertisement of `inner` to a capture on its way to the
+/// sink, for `--record`. Writing stops at the first error, which `stop`
+/// returns.
+pub struct Recorder<S, W: Write> {
+    inner: S,
+    writer: Arc<Mutex<CaptureWriter<W>>>,
+    failed: Arc<Mutex<Option<anyhow::Error>>>,
+}
+
+impl<S, W: Write> Recorder<S, W> {
+    pub fn new(inner: S, writer: CaptureWriter<W>) -> Self {
+        Recorder {
+            inner,
+            writer: Arc::new(Mutex::new(writer)),
+            failed: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl<S, W> AdvertisementSource for Recorder<S, W>
+where
+    S: AdvertisementSource,
+    W: Write + Send + 'static,
+{
+    fn start(&mut self, mut sink: Sink) -> anyhow::Result<()> {
+        let writer = self.writer.clone();
+        let failed = self.failed.clone();
+        self.inner.start(Box::new(move |advert: Advertisement| {
+            let mut failed = failed.lock().unwrap();
+            if failed.is_none()
+                && let Err(err) = writer.lock().unwrap().write(&advert)
+            {
+                *failed = Some(err);
+            }
+            drop(failed);
+            sink(advert)
+        }))
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        self.inner.stop()?;
+        match self.failed.lock().unwrap().take() {
+            Some(err) => Err(err.context("writing the capture")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads a capture written by `CaptureWriter`. Empty lines are skipped.
+pub fn read(reader: impl BufRead) -> anyhow::Result<Vec<Advertisement>> {
+    let mut adverts = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let advert = serde_json::from_str::<Record>(&line)
+            .map_err(anyhow::Error::from)
+            .and_then(Advertisement::try_from)
+            .with_context(|| format!("line {}", index + 1))?;
+        adverts.push(advert);
+    }
+    Ok(adverts)
+}
+
+pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Vec<Advertisement>> {
+    let path = path.as_ref();
+    let file =
+        File::open(path).with_context(|| format!("opening capture file {}", path.display()))?;
+    read(BufReader::new(file)).with_context(|| format!("reading capture file {}", path.display()))
+}
+
+/// Strips what identifies a user from advertisements, so captures can be
+/// attached to bug reports:
+///
+/// - addresses are replaced by `00:00:00:00:00:01`, `...:02` and so on in
+///   order of appearance, keeping the two address type bits,
+/// - the encrypted tail of proximity pairing messages is zeroed, like
+///   `AirPods::desensitize`,
+/// - other Continuity messages and other companies' data are dropped, as
+///   they carry hashed identifiers.
+#[derive(Debug, Clone, Default)]
+pub struct Scrubber {
+    addresses: HashMap<u64, u64>,
+}
+
+impl Scrubber {
+    pub fn new() -> Self {
+        Scrubber {
+            addresses: HashMap::new(),
+        }
+    }
+
+    pub fn scrub(&mut self, advert: &Advertisement) -> Advertisement {
+        let next = self.addresses.len() as u64 + 1;
+        let pseudonym = *self.addresses.entry(advert.address).or_insert(next);
+
+        let manufacturer_data = advert
+            .apple_data()
+            .and_then(scrub_apple_data)
+            .map(|data| vec![(VENDOR_ID, data)])
+            .unwrap_or_default();
+        Advertisement {
+            address: (advert.address & 0xC000_0000_0000) | pseudonym,
+            manufacturer_data,
+            ..advert.clone()
+        }
+    }
+}
+
+/// Keeps only the desensitized proximity pairing message of an Apple blob.
+fn scrub_apple_data(data: &[u8]) -> Option<Vec<u8>> {
+    Tlvs::new(data).filter_map(Result::ok).find_map(|tlv| {
+        let mut message = vec![tlv.kind, tlv.value.len() as u8];
+        message.extend_from_slice(tlv.value);
+        let ap = as_airpods(&message).ok()?;
+        ap.desensitize().to_bytes().ok()
+    })
+}
+
+fn format_address(address: u64) -> String {
+    let bytes = address.to_be_bytes();
+    bytes[2..]
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn parse_address(s: &str) -> anyhow::Result<u64> {
+    let bytes = from_hex(&s.replace(':', ""))?;
+    if bytes.len() != 6 {
+        bail!("invalid address {:?}", s);
+    }
+    Ok(bytes
+        .iter()
+        .fold(0u64, |address, byte| (address << 8) | u64::from(*byte)))
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(s: &str) -> anyhow::Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        bail!("odd number of hex digits in {:?}", s);
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .with_context(|| format!("invalid hex {:?}", s))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{
+        airpod::{AirPods, AirPodsState},
+        source::MemorySource,
+    };
+
+    fn advert(address: u64) -> Advertisement {
+        Advertisement {
+            address,
+            address_type: AddressType::Random,
+            rssi: Some(-52),
+            tx_power: None,
+            at: SystemTime::UNIX_EPOCH + Duration::from_millis(1_760_000_000_123),
+            manufacturer_data: vec![(VENDOR_ID, vec![0x10, 0x02, 0xAB, 0xCD]), (0x0006, vec![1])],
+        }
+    }
+
+    #[test]
+    fn round_trips_adverts() {
+        let mut capture = Vec::new();
+        let mut writer = CaptureWriter::new(&mut capture);
+        writer.write(&advert(0x4A11_2233_4455)).unwrap();
+        writer.write(&advert(0x0011_2233_4455)).unwrap();
+
+        let adverts = read(capture.as_slice()).unwrap();
+        assert_eq!(adverts.len(), 2);
+        for (read, written) in adverts.iter().zip([0x4A11_2233_4455, 0x0011_2233_4455]) {
+            let written = advert(written);
+            assert_eq!(read.address, written.address);
+            assert_eq!(read.at, written.at);
+            assert_eq!(read.rssi, written.rssi);
+            assert_eq!(read.manufacturer_data, written.manufacturer_data);
+        }
+    }
+
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn recorder_returns_the_write_error_from_stop() {
+        let source = MemorySource::new(vec![advert(1), advert(2)]);
+        let mut recorder = Recorder::new(source, CaptureWriter::new(Broken));
+
+        let delivered = Arc::new(Mutex::new(0));
+        let counter = delivered.clone();
+        recorder
+            .start(Box::new(move |_| {
+                *counter.lock().unwrap() += 1;
+                true
+            }))
+            .unwrap();
+
+        // Adverts still reach the sink
+        assert_eq!(*delivered.lock().unwrap(), 2);
+        let err = recorder.stop().unwrap_err();
+        assert!(format!("{:#}", err).contains("disk full"));
+        assert!(recorder.stop().is_ok());
+    }
+
+    #[test]
+    fn scrubber_hides_addresses_and_secrets() {
+        let mut ap = as_airpods(&AirPodsState::sample().encode()).unwrap();
+        ap.unk12 = [0xAA; 16];
+        // Nearby Info before the proximity pairing message
+        let mut apple = vec![0x10, 0x02, 0x15, 0x1C];
+        apple.extend(ap.to_bytes().unwrap());
+        let advert = |address| Advertisement {
+            manufacturer_data: vec![(0x0006, vec![1, 2, 3]), (VENDOR_ID, apple.clone())],
+            ..advert(address)
+        };
+
+        let mut scrubber = Scrubber::new();
+        let first = scrubber.scrub(&advert(0x4A11_2233_4455));
+        let second = scrubber.scrub(&advert(0xC011_2233_4455));
+        let again = scrubber.scrub(&advert(0x4A11_2233_4455));
+
+        // Pseudonyms keep the address type bits
+        assert_eq!(first.address, 0x4000_0000_0001);
+        assert_eq!(second.address, 0xC000_0000_0002);
+        assert_eq!(again.address, first.address);
+
+        let [(VENDOR_ID, data)] = first.manufacturer_data.as_slice() else {
+            panic!("expected only the Apple data");
+        };
+        let scrubbed = as_airpods(data).unwrap();
+        assert_eq!(data.len(), AirPods::LEN);
+        assert_eq!(scrubbed.unk12, [0; 16]);
+        assert_eq!(AirPodsState::from(&scrubbed), AirPodsState::from(&ap));
+        assert_eq!(first.rssi, Some(-52));
+    }
+}
@@ -0,0 +1,32 @@
+use crate::source::{Advertisement, AdvertisementSource, Sink};
+
+/// Hands out a fixed list of advertisements, all from within `start`.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    adverts: Vec<Advertisement>,
+}
+
+impl MemorySource {
+    pub fn new(adverts: Vec<Advertisement>) -> Self {
+        MemorySource { adverts }
+    }
+
+    pub fn push(&mut self, advert: Advertisement) {
+        self.adverts.push(advert);
+    }
+}
+
+impl AdvertisementSource for MemorySource {
+    fn start(&mut self, mut sink: Sink) -> anyhow::Result<()> {
+        for advert in self.adverts.iter().cloned() {
+            if !sink(advert) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+//! Where advertisements come from, independent of the Bluetooth stack.
+
+pub mod capture;
+pub mod memory;
+pub mod replay;
+
+pub use capture::{CaptureWriter, Recorder, Scrubber};
+pub use memory::MemorySource;
+pub use replay::ReplaySource;
+
+use std::time::SystemTime;
+
+use crate::airpod::VENDOR_ID;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    Public,
+    Random,
+    Unknown,
+}
+
+/// One received advertisement, as reported by the scanner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advertisement {
+    pub address: u64,
+    pub address_type: AddressType,
+    pub rssi: Option<i16>,
+    pub tx_power: Option<i16>,
+    pub at: SystemTime,
+    /// Manufacturer specific data entries as (company id, data).
+    pub manufacturer_data: Vec<(u16, Vec<u8>)>,
+}
+
+impl Advertisement {
+    pub fn manufacturer_data(&self, company_id: u16) -> Option<&[u8]> {
+        self.manufacturer_data
+            .iter()
+            .find(|(id, _)| *id == company_id)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Apple Continuity payload, if the advert carries one.
+    pub fn apple_data(&self) -> Option<&[u8]> {
+        self.manufacturer_data(VENDOR_ID)
+    }
+}
+
+/// Receives advertisements from a source. Returning `false` asks the source
+/// to stop delivering.
+pub type Sink = Box<dyn FnMut(Advertisement) -> bool + Send>;
+
+/// A BLE scanner, or anything standing in for one.
+pub trait AdvertisementSource {
+    /// Starts delivering advertisements to `sink`. Sources backed by a radio
+    /// deliver from their own thread and return right away.
+    fn start(&mut self, sink: Sink) -> anyhow::Result<()>;
+
+    /// Stops delivering; the sink is dropped once this returns.
+    fn stop(&mut self) -> anyhow::Result<()>;
+}
+
+impl<S: AdvertisementSource + ?Sized> AdvertisementSource for Box<S> {
+    fn start(&mut self, sink: Sink) -> anyhow::Result<()> {
+        (**self).start(sink)
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        (**self).stop()
+    }
+}
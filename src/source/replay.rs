@@ -0,0 +1,157 @@
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+
+use crate::source::{Advertisement, AdvertisementSource, Sink, capture};
+
+/// Plays recorded advertisements back from a thread, keeping the gaps
+/// between their timestamps.
+#[derive(Debug)]
+pub struct ReplaySource {
+    adverts: Vec<Advertisement>,
+    speed: f64,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ReplaySource {
+    pub fn new(adverts: Vec<Advertisement>) -> Self {
+        ReplaySource {
+            adverts,
+            speed: 1.0,
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Replays a capture file written by `CaptureWriter`.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(Self::new(capture::load(path)?))
+    }
+
+    /// Plays `speed` times faster than recorded; zero or less plays
+    /// without any delay.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// True until every advertisement was played or `stop` was called.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+impl AdvertisementSource for ReplaySource {
+    fn start(&mut self, mut sink: Sink) -> anyhow::Result<()> {
+        if self.thread.is_some() {
+            return Err(anyhow!("replay already started"));
+        }
+
+        let adverts = self.adverts.clone();
+        let speed = self.speed;
+        let running = self.running.clone();
+        running.store(true, Ordering::SeqCst);
+        self.thread = Some(thread::spawn(move || {
+            let mut prev = None;
+            for advert in adverts {
+                if let Some(prev) = prev {
+                    let gap = advert.at.duration_since(prev).unwrap_or_default();
+                    if speed > 0.0 {
+                        // Too slow to represent, wait until stopped
+                        let gap = Duration::try_from_secs_f64(gap.as_secs_f64() / speed)
+                            .unwrap_or(Duration::MAX);
+                        sleep_while(&running, gap);
+                    }
+                }
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+                prev = Some(advert.at);
+                if !sink(advert) {
+                    break;
+                }
+            }
+            running.store(false, Ordering::SeqCst);
+        }));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            thread
+                .join()
+                .map_err(|_| anyhow!("replay thread panicked"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ReplaySource {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl Drop for ReplaySource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Sleeps for `duration` in small steps so `stop` doesn't wait out long gaps.
+fn sleep_while(running: &AtomicBool, duration: Duration) {
+    const STEP: Duration = Duration::from_millis(50);
+    let mut left = duration;
+    while !left.is_zero() && running.load(Ordering::SeqCst) {
+        let step = left.min(STEP);
+        thread::sleep(step);
+        left -= step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::SystemTime};
+
+    use super::*;
+    use crate::source::AddressType;
+
+    fn advert(at: SystemTime) -> Advertisement {
+        Advertisement {
+            address: 1,
+            address_type: AddressType::Random,
+            rssi: Some(-60),
+            tx_power: None,
+            at,
+            manufacturer_data: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn waits_until_stopped_when_too_slow_to_represent() {
+        let start = SystemTime::UNIX_EPOCH;
+        let adverts = vec![advert(start), advert(start + Duration::from_secs(1))];
+        let mut replay = ReplaySource::new(adverts).with_speed(1e-300);
+
+        let (tx, rx) = mpsc::channel();
+        replay
+            .start(Box::new(move |advert| tx.send(advert).is_ok()))
+            .unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap().at, start);
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        assert!(replay.is_running());
+
+        replay.stop().unwrap();
+        assert!(!replay.is_running());
+    }
+}
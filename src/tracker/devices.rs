@@ -0,0 +1,327 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    airpod::{AirPods, as_airpods},
+    source::Advertisement,
+    tracker::{ChangeTracker, Event, Measure, MergedState, OwnershipResolver, Proof, RssiFilter},
+};
+
+/// What a device is known by: the name of a resolved identity, or the
+/// advertiser address when no IRK matched it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeviceKey {
+    Identity(String),
+    Address(u64),
+}
+
+impl fmt::Display for DeviceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceKey::Identity(name) => write!(f, "{}", name),
+            DeviceKey::Address(address) => {
+                let bytes = address.to_be_bytes();
+                write!(
+                    f,
+                    "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+                    bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]
+                )
+            }
+        }
+    }
+}
+
+/// One nearby device and what was last heard from it.
+#[derive(Clone)]
+pub struct Device {
+    pub key: DeviceKey,
+    /// Address of the latest advert; rotates for resolvable private addresses.
+    pub address: u64,
+    pub last: AirPods,
+    /// RSSI of the latest advert in dBm.
+    pub rssi: Option<i16>,
+    /// Advertised transmit power in dBm, if the advert carried it.
+    pub tx_power: Option<i16>,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+    pub adverts: u64,
+    /// The latest advert came from a device paired with this computer, see
+    /// `OwnershipResolver`.
+    pub mine: bool,
+    signal: RssiFilter,
+    changes: ChangeTracker,
+}
+
+impl Device {
+    pub fn smoothed_rssi(&self) -> Option<f32> {
+        self.signal.value()
+    }
+
+    /// Smoothed signal loss in dB, comparable across devices that
+    /// advertise different transmit powers.
+    pub fn path_loss(&self) -> Option<f32> {
+        Some(f32::from(self.tx_power?) - self.smoothed_rssi()?)
+    }
+
+    /// How close the device seems in dB by `measure`, higher is closer.
+    /// Only comparable between devices measured the same way.
+    pub fn proximity(&self, measure: Measure) -> Option<f32> {
+        match measure {
+            Measure::PathLoss => self.path_loss().map(|loss| -loss),
+            Measure::Rssi => self.smoothed_rssi(),
+        }
+    }
+
+    pub fn state(&self) -> &MergedState {
+        self.changes.state()
+    }
+}
+
+/// Nearby devices keyed by identity or address, so several pairs in the
+/// same room don't overwrite each other.
+#[derive(Clone)]
+pub struct DeviceTable {
+    owners: OwnershipResolver,
+    devices: HashMap<DeviceKey, Device>,
+    lost_after: Duration,
+}
+
+impl DeviceTable {
+    pub fn new() -> Self {
+        DeviceTable {
+            owners: OwnershipResolver::new(),
+            devices: HashMap::new(),
+            lost_after: ChangeTracker::DEFAULT_LOST_AFTER,
+        }
+    }
+
+    /// Devices not heard from for `lost_after` are dropped by `expire`.
+    pub fn with_lost_after(mut self, lost_after: Duration) -> Self {
+        self.lost_after = lost_after;
+        self
+    }
+
+    pub fn lost_after(&self) -> Duration {
+        self.lost_after
+    }
+
+    /// Adverts recognised by `owners` mark their device as `mine`; those
+    /// resolved through an IRK are also keyed by the paired name.
+    pub fn with_owners(mut self, owners: OwnershipResolver) -> Self {
+        self.owners = owners;
+        self
+    }
+
+    /// Only an IRK links rotating addresses, anything else stays keyed by
+    /// address so a stranger's adverts never land on a paired device.
+    pub fn key_for(&self, address: u64) -> DeviceKey {
+        match self.owners.recognise(address) {
+            Some((owner, Proof::Irk)) => DeviceKey::Identity(owner.name.clone()),
+            _ => DeviceKey::Address(address),
+        }
+    }
+
+    /// Records an advert from `address` and returns the device it belongs
+    /// to along with what changed.
+    pub fn update(
+        &mut self,
+        address: u64,
+        rssi: Option<i16>,
+        tx_power: Option<i16>,
+        ap: &AirPods,
+        at: SystemTime,
+    ) -> (&Device, Vec<Event>) {
+        let mine = self.owners.owner(address).is_some();
+        let key = self.key_for(address);
+        let lost_after = self.lost_after;
+        let device = self.devices.entry(key.clone()).or_insert_with(|| Device {
+            key,
+            address,
+            last: ap.clone(),
+            rssi,
+            tx_power,
+            first_seen: at,
+            last_seen: at,
+            adverts: 0,
+            mine,
+            signal: RssiFilter::default(),
+            changes: ChangeTracker::new(lost_after),
+        });
+
+        device.address = address;
+        device.last = ap.clone();
+        if let Some(rssi) = rssi {
+            device.rssi = Some(rssi);
+            device.signal.update(rssi);
+        }
+        device.tx_power = tx_power.or(device.tx_power);
+        device.last_seen = at;
+        device.adverts += 1;
+        device.mine = mine;
+        let events = device.changes.update(ap, at);
+        (device, events)
+    }
+
+    /// Records a raw advertisement if it carries a proximity pairing
+    /// message, see `update`.
+    pub fn ingest(&mut self, advert: &Advertisement) -> Option<(&Device, Vec<Event>)> {
+        let ap = as_airpods(advert.apple_data()?).ok()?;
+        Some(self.update(advert.address, advert.rssi, advert.tx_power, &ap, advert.at))
+    }
+
+    /// Drops the devices that have gone silent and reports them as lost.
+    pub fn expire(&mut self, now: SystemTime) -> Vec<(DeviceKey, Event)> {
+        let mut lost = Vec::new();
+        self.devices
+            .retain(|key, device| match device.changes.tick(now) {
+                Some(event) => {
+                    lost.push((key.clone(), event));
+                    false
+                }
+                None => true,
+            });
+        lost
+    }
+
+    pub fn get(&self, key: &DeviceKey) -> Option<&Device> {
+        self.devices.get(key)
+    }
+
+    /// Looks up the device an advertiser address belongs to.
+    pub fn find(&self, address: u64) -> Option<&Device> {
+        self.get(&self.key_for(address))
+            .or_else(|| self.iter().find(|device| device.address == address))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Device> {
+        self.devices.values()
+    }
+
+    /// Devices ordered by when they were first seen.
+    pub fn list(&self) -> Vec<&Device> {
+        let mut devices: Vec<_> = self.iter().collect();
+        devices.sort_by_key(|device| (device.first_seen, device.key.clone()));
+        devices
+    }
+
+    /// Devices ordered from the nearest to the farthest by
+    /// `Device::proximity`, measured as `Measure::common`; devices without
+    /// RSSI come last.
+    pub fn ranked(&self) -> Vec<&Device> {
+        let mut devices: Vec<_> = self.iter().collect();
+        let measure = Measure::common(self.iter());
+        devices.sort_by(|a, b| {
+            let proximity =
+                |device: &Device| device.proximity(measure).unwrap_or(f32::NEG_INFINITY);
+            proximity(b).total_cmp(&proximity(a))
+        });
+        devices
+    }
+
+    /// Devices paired with this computer; the rest are strangers' and
+    /// usually hidden.
+    pub fn mine(&self) -> impl Iterator<Item = &Device> {
+        self.iter().filter(|device| device.mine)
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+}
+
+impl Default for DeviceTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        airpod::{AirPodsState, Irk},
+        tracker::{NearestSelector, PairedDevice},
+    };
+
+    const IRK: Irk = Irk([0x42; 16]);
+
+    fn airpods() -> AirPods {
+        as_airpods(&AirPodsState::sample().encode()).unwrap()
+    }
+
+    /// Resolvable private address generated from `irk`.
+    fn rpa(irk: &Irk, prand: u32) -> u64 {
+        let prand = (prand & 0x3F_FFFF) | 0x40_0000;
+        (u64::from(prand) << 24) | u64::from(irk.ah(prand))
+    }
+
+    fn table() -> DeviceTable {
+        let mut owners = OwnershipResolver::new();
+        owners.add(PairedDevice::new("My AirPods").with_irk(IRK));
+        DeviceTable::default().with_owners(owners)
+    }
+
+    #[test]
+    fn resolved_addresses_merge_into_the_owner() {
+        let mut table = table();
+        let now = SystemTime::now();
+        for prand in [1, 2, 3] {
+            let (device, _) = table.update(rpa(&IRK, prand), Some(-50), None, &airpods(), now);
+            assert_eq!(device.key, DeviceKey::Identity("My AirPods".into()));
+            assert!(device.mine);
+        }
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn strangers_stay_apart_from_the_owner() {
+        let mut table = table();
+        let now = SystemTime::now();
+        table.update(rpa(&IRK, 1), Some(-50), None, &airpods(), now);
+
+        let stranger = rpa(&Irk([0x17; 16]), 1);
+        let (device, _) = table.update(stranger, Some(-60), None, &airpods(), now);
+        assert_eq!(device.key, DeviceKey::Address(stranger));
+        assert!(!device.mine);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.mine().count(), 1);
+    }
+
+    #[test]
+    fn ranks_by_path_loss_when_every_device_has_tx_power() {
+        let mut table = DeviceTable::default();
+        let now = SystemTime::now();
+        // Louder, but only because it transmits 12 dB hotter
+        table.update(1, Some(-50), Some(12), &airpods(), now);
+        table.update(2, Some(-55), Some(0), &airpods(), now);
+
+        let ranked: Vec<_> = table.ranked().iter().map(|device| device.address).collect();
+        assert_eq!(ranked, [2, 1]);
+
+        let mut selector = NearestSelector::default();
+        assert_eq!(selector.select_in(&table).unwrap().address, 2);
+    }
+
+    #[test]
+    fn ranks_by_rssi_when_a_device_lacks_tx_power() {
+        let mut table = DeviceTable::default();
+        let now = SystemTime::now();
+        table.update(1, Some(-50), Some(12), &airpods(), now);
+        table.update(2, Some(-55), Some(0), &airpods(), now);
+        table.update(3, Some(-70), None, &airpods(), now);
+        assert_eq!(Measure::common(table.iter()), Measure::Rssi);
+
+        let ranked: Vec<_> = table.ranked().iter().map(|device| device.address).collect();
+        assert_eq!(ranked, [1, 2, 3]);
+
+        let mut selector = NearestSelector::default();
+        assert_eq!(selector.select_in(&table).unwrap().address, 1);
+    }
+}
@@ -0,0 +1,237 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    airpod::{AirPods, BatteryStatus, Component, Side},
+    tracker::{LidEvent, LidTracker, MergedState, PairMerger},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// First advert of the device, or first one after it was lost.
+    DeviceAppeared,
+    /// No advert for longer than the tracker's timeout.
+    DeviceLost,
+    InEarChanged {
+        side: Side,
+        in_ear: bool,
+    },
+    /// A single unit was put on or taken off.
+    OnHeadChanged {
+        on_head: bool,
+    },
+    /// Both buds went back into the case.
+    BothInCase,
+    Lid(LidEvent),
+    ChargingStarted(Component),
+    ChargingStopped(Component),
+    BatteryLevelChanged {
+        component: Component,
+        from: BatteryStatus,
+        to: BatteryStatus,
+    },
+    /// The component dropped to a low level while not charging.
+    LowBattery(Component),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub at: SystemTime,
+    pub kind: EventKind,
+}
+
+/// Diffs successive adverts of one device into typed change events, so
+/// consumers don't have to compare `AirPods` snapshots themselves.
+#[derive(Debug, Clone)]
+pub struct ChangeTracker {
+    merger: PairMerger,
+    lid: LidTracker,
+    last: Option<MergedState>,
+    last_seen: Option<SystemTime>,
+    lost_after: Duration,
+}
+
+impl ChangeTracker {
+    pub const DEFAULT_LOST_AFTER: Duration = Duration::from_secs(30);
+
+    pub fn new(lost_after: Duration) -> Self {
+        ChangeTracker {
+            merger: PairMerger::new(),
+            lid: LidTracker::new(),
+            last: None,
+            last_seen: None,
+            lost_after,
+        }
+    }
+
+    /// Feeds the next advert of the device and returns what changed. Single
+    /// units have no case, lid or buds, so they only report `Unit` battery
+    /// and on-head changes.
+    pub fn update(&mut self, ap: &AirPods, at: SystemTime) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut push = |kind| events.push(Event { at, kind });
+
+        if self.last_seen.is_none() {
+            push(EventKind::DeviceAppeared);
+        }
+        self.last_seen = Some(at);
+
+        if !ap.capabilities().single_unit
+            && let Some(lid) = self.lid.update(ap.lid)
+        {
+            push(EventKind::Lid(lid));
+        }
+
+        let next = *self.merger.update(ap, at);
+        if let Some(prev) = self.last.replace(next) {
+            for side in [Side::Left, Side::Right] {
+                if let (Some(was), Some(now)) = (prev.in_ear(side), next.in_ear(side))
+                    && was.value != now.value
+                {
+                    push(EventKind::InEarChanged {
+                        side,
+                        in_ear: now.value,
+                    });
+                }
+            }
+
+            if let (Some(was), Some(now)) = (prev.on_head, next.on_head)
+                && was.value != now.value
+            {
+                push(EventKind::OnHeadChanged { on_head: now.value });
+            }
+
+            let both_in_case = |state: &MergedState| state.both_in_case.is_some_and(|v| v.value);
+            if !both_in_case(&prev) && both_in_case(&next) {
+                push(EventKind::BothInCase);
+            }
+
+            for component in Component::ALL {
+                let (Some(was), Some(now)) = (prev.battery(component), next.battery(component))
+                else {
+                    continue;
+                };
+                let (was, now) = (was.value, now.value);
+                if !was.charging && now.charging {
+                    push(EventKind::ChargingStarted(component));
+                }
+                if was.charging && !now.charging {
+                    push(EventKind::ChargingStopped(component));
+                }
+                if was.level != now.level {
+                    push(EventKind::BatteryLevelChanged {
+                        component,
+                        from: was,
+                        to: now,
+                    });
+                }
+                if !now.charging && now.is_low_battery() && !was.is_low_battery() {
+                    push(EventKind::LowBattery(component));
+                }
+            }
+        }
+        events
+    }
+
+    /// Reports the device as lost once it has been silent for too long.
+    /// Call periodically, e.g. from the scan loop.
+    pub fn tick(&mut self, now: SystemTime) -> Option<Event> {
+        let last_seen = self.last_seen?;
+        let silent = now.duration_since(last_seen).unwrap_or_default();
+        if silent < self.lost_after {
+            return None;
+        }
+
+        *self = Self::new(self.lost_after);
+        Some(Event {
+            at: now,
+            kind: EventKind::DeviceLost,
+        })
+    }
+
+    pub fn last_seen(&self) -> Option<SystemTime> {
+        self.last_seen
+    }
+
+    pub fn state(&self) -> &MergedState {
+        self.merger.state()
+    }
+}
+
+impl Default for ChangeTracker {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LOST_AFTER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airpod::{AirPodsState, BatteryLevel, Model, as_airpods};
+
+    fn kinds(tracker: &mut ChangeTracker, state: AirPodsState) -> Vec<EventKind> {
+        let ap = as_airpods(&state.encode()).unwrap();
+        let events = tracker.update(&ap, SystemTime::now());
+        events.into_iter().map(|event| event.kind).collect()
+    }
+
+    #[test]
+    fn reports_case_and_lid_changes_of_earbuds() {
+        let mut tracker = ChangeTracker::default();
+        let worn = AirPodsState::sample();
+        assert_eq!(kinds(&mut tracker, worn), [EventKind::DeviceAppeared]);
+
+        let docked = AirPodsState {
+            left_in_ear: false,
+            right_in_ear: false,
+            both_in_case: true,
+            lid_opened: true,
+            lid_switch_count: 1,
+            ..worn
+        };
+        let events = kinds(&mut tracker, docked);
+        assert!(events.contains(&EventKind::Lid(LidEvent::Opened { missed: 0 })));
+        assert!(events.contains(&EventKind::BothInCase));
+        assert!(events.contains(&EventKind::InEarChanged {
+            side: Side::Left,
+            in_ear: false
+        }));
+    }
+
+    #[test]
+    fn reports_single_units_without_case_lid_or_buds() {
+        let mut tracker = ChangeTracker::default();
+        let worn = AirPodsState {
+            model_id: Model::AirPodsMax.id(),
+            ..AirPodsState::sample()
+        };
+        assert_eq!(kinds(&mut tracker, worn), [EventKind::DeviceAppeared]);
+
+        let off = AirPodsState {
+            left: BatteryStatus {
+                level: BatteryLevel::Percent(70),
+                charging: false,
+            },
+            left_in_ear: false,
+            right_in_ear: false,
+            both_in_case: true,
+            lid_opened: true,
+            lid_switch_count: 1,
+            case: BatteryStatus {
+                level: BatteryLevel::Percent(10),
+                charging: true,
+            },
+            ..worn
+        };
+        assert_eq!(
+            kinds(&mut tracker, off),
+            [
+                EventKind::OnHeadChanged { on_head: false },
+                EventKind::BatteryLevelChanged {
+                    component: Component::Unit,
+                    from: worn.left,
+                    to: off.left,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::airpod::packet::Lid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LidEvent {
+    /// `missed` counts the toggles that happened between two adverts
+    /// before this one, e.g. a quick close and reopen.
+    Opened {
+        missed: u8,
+    },
+    Closed {
+        missed: u8,
+    },
+}
+
+/// Turns the lid byte of successive adverts from one device into lid
+/// edges, using `Lid::switch_count` to catch toggles between adverts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LidTracker {
+    last: Option<Lid>,
+}
+
+impl LidTracker {
+    pub fn new() -> Self {
+        LidTracker { last: None }
+    }
+
+    /// Number of toggles between two lid samples, accounting for the
+    /// 3-bit counter wrapping around.
+    pub fn toggles_between(prev: Lid, next: Lid) -> u8 {
+        next.switch_count().wrapping_sub(prev.switch_count()) & 0b0000_0111
+    }
+
+    /// Feeds the lid byte of the next advert. The first advert only sets
+    /// the baseline and never yields an event.
+    pub fn update(&mut self, lid: Lid) -> Option<LidEvent> {
+        let prev = self.last.replace(lid)?;
+        let toggles = Self::toggles_between(prev, lid);
+        if toggles == 0 && prev.closed() == lid.closed() {
+            return None;
+        }
+
+        let missed = toggles.saturating_sub(1);
+        if lid.closed() {
+            Some(LidEvent::Closed { missed })
+        } else {
+            Some(LidEvent::Opened { missed })
+        }
+    }
+
+    pub fn is_closed(&self) -> Option<bool> {
+        self.last.map(|lid| lid.closed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lid(closed: bool, switch_count: u8) -> Lid {
+        let mut lid = Lid { bits: 0 };
+        lid.set_closed(closed);
+        lid.set_switch_count(switch_count);
+        lid
+    }
+
+    fn tracker(baseline: Lid) -> LidTracker {
+        let mut tracker = LidTracker::new();
+        assert_eq!(tracker.update(baseline), None);
+        tracker
+    }
+
+    #[test]
+    fn counts_missed_toggles_across_the_wrap_around() {
+        assert_eq!(LidTracker::toggles_between(lid(false, 7), lid(true, 2)), 3);
+        let mut tracker = tracker(lid(false, 7));
+        assert_eq!(
+            tracker.update(lid(true, 2)),
+            Some(LidEvent::Closed { missed: 2 })
+        );
+        assert_eq!(tracker.is_closed(), Some(true));
+    }
+
+    #[test]
+    fn ignores_an_unchanged_lid() {
+        let mut tracker = tracker(lid(true, 5));
+        assert_eq!(tracker.update(lid(true, 5)), None);
+        assert_eq!(tracker.is_closed(), Some(true));
+    }
+
+    #[test]
+    fn reports_a_state_change_without_a_counter_change() {
+        let mut tracker = tracker(lid(false, 3));
+        assert_eq!(
+            tracker.update(lid(true, 3)),
+            Some(LidEvent::Closed { missed: 0 })
+        );
+        assert_eq!(
+            tracker.update(lid(false, 4)),
+            Some(LidEvent::Opened { missed: 0 })
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::{fmt, time::SystemTime};
+
+use crate::airpod::{AirPods, BatteryLevel, BatteryStatus, Component, DeviceView, Side};
+
+/// A value together with the bud that advertised it and when.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub from: Side,
+    pub at: SystemTime,
+}
+
+/// State of a device put together from its adverts, shaped like
+/// `DeviceView`: earbuds fill the per-bud and case fields, single units
+/// only `unit` and `on_head`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergedState {
+    pub left: Option<Sourced<BatteryStatus>>,
+    pub right: Option<Sourced<BatteryStatus>>,
+    pub case: Option<Sourced<BatteryStatus>>,
+    pub left_in_ear: Option<Sourced<bool>>,
+    pub right_in_ear: Option<Sourced<bool>>,
+    pub both_in_case: Option<Sourced<bool>>,
+    pub lid_opened: Option<Sourced<bool>>,
+    /// Battery of a single unit, see `DeviceView::SingleUnit`.
+    pub unit: Option<Sourced<BatteryStatus>>,
+    pub on_head: Option<Sourced<bool>>,
+}
+
+impl MergedState {
+    pub fn is_single_unit(&self) -> bool {
+        self.unit.is_some()
+    }
+
+    pub fn battery(&self, component: Component) -> Option<&Sourced<BatteryStatus>> {
+        match component {
+            Component::Left => self.left.as_ref(),
+            Component::Right => self.right.as_ref(),
+            Component::Case => self.case.as_ref(),
+            Component::Unit => self.unit.as_ref(),
+        }
+    }
+
+    pub fn in_ear(&self, side: Side) -> Option<&Sourced<bool>> {
+        match side {
+            Side::Left => self.left_in_ear.as_ref(),
+            Side::Right => self.right_in_ear.as_ref(),
+        }
+    }
+}
+
+impl fmt::Display for MergedState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn show<T: fmt::Display>(val: &Option<Sourced<T>>) -> String {
+            match val {
+                Some(val) => format!("{} (from {})", val.value, val.from.as_str()),
+                None => "-".to_string(),
+            }
+        }
+        if self.is_single_unit() {
+            return write!(
+                f,
+                "Battery: {}\nOn Head: {}",
+                show(&self.unit),
+                show(&self.on_head)
+            );
+        }
+        write!(
+            f,
+            "Left Battery: {}\n\
+            Right Battery: {}\n\
+            Case Battery: {}\n\
+            Both in Case: {}\n\
+            Lid Opened: {}\n\
+            Left In Ear: {}\n\
+            Right In Ear: {}",
+            show(&self.left),
+            show(&self.right),
+            show(&self.case),
+            show(&self.both_in_case),
+            show(&self.lid_opened),
+            show(&self.left_in_ear),
+            show(&self.right_in_ear)
+        )
+    }
+}
+
+/// Combines the alternating broadcasts of the left and right bud, which
+/// each report from their own perspective, into one stable state.
+#[derive(Debug, Clone, Default)]
+pub struct PairMerger {
+    state: MergedState,
+}
+
+impl PairMerger {
+    pub fn new() -> Self {
+        PairMerger {
+            state: MergedState::default(),
+        }
+    }
+
+    /// Folds in the next advert of the device, keeping the freshest value
+    /// of every component. A battery reading that is not understood does
+    /// not replace a known one.
+    pub fn update(&mut self, ap: &AirPods, at: SystemTime) -> &MergedState {
+        let from = ap.broadcasting_side();
+        let sourced = |value| Some(Sourced { value, from, at });
+
+        let state = &mut self.state;
+        match ap.view() {
+            DeviceView::Earbuds {
+                left,
+                right,
+                case,
+                left_in_ear,
+                right_in_ear,
+                both_in_case,
+                lid_opened,
+            } => {
+                merge_battery(&mut state.left, left, from, at);
+                merge_battery(&mut state.right, right, from, at);
+                merge_battery(&mut state.case, case, from, at);
+                state.left_in_ear = sourced(left_in_ear);
+                state.right_in_ear = sourced(right_in_ear);
+                state.both_in_case = sourced(both_in_case);
+                state.lid_opened = sourced(lid_opened);
+            }
+            DeviceView::SingleUnit { battery, on_head } => {
+                merge_battery(&mut state.unit, battery, from, at);
+                state.on_head = sourced(on_head);
+            }
+        }
+        &self.state
+    }
+
+    pub fn state(&self) -> &MergedState {
+        &self.state
+    }
+}
+
+fn merge_battery(
+    slot: &mut Option<Sourced<BatteryStatus>>,
+    value: BatteryStatus,
+    from: Side,
+    at: SystemTime,
+) {
+    let keep_old = matches!(value.level, BatteryLevel::Unknown(_)) && slot.is_some();
+    if !keep_old {
+        *slot = Some(Sourced { value, from, at });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airpod::{AirPodsState, Model, as_airpods};
+
+    fn merged(model: Model) -> MergedState {
+        let state = AirPodsState {
+            model_id: model.id(),
+            ..AirPodsState::sample()
+        };
+        let ap = as_airpods(&state.encode()).unwrap();
+        *PairMerger::new().update(&ap, SystemTime::now())
+    }
+
+    #[test]
+    fn merges_earbuds_with_their_case() {
+        let state = merged(Model::AirPodsPro2);
+        assert!(!state.is_single_unit());
+        assert!(state.case.is_some() && state.lid_opened.is_some());
+        assert!(state.to_string().contains("Case Battery: 50%"));
+    }
+
+    #[test]
+    fn merges_single_units_without_a_case() {
+        let state = merged(Model::AirPodsMax);
+        assert!(state.is_single_unit());
+        assert_eq!(state.unit.unwrap().value.percent(), Some(80));
+        assert_eq!(state.on_head.map(|on_head| on_head.value), Some(true));
+        assert!(state.case.is_none() && state.lid_opened.is_none());
+        assert!(state.left.is_none() && state.left_in_ear.is_none());
+
+        let shown = state.to_string();
+        assert!(shown.starts_with("Battery: 80%"), "{}", shown);
+        assert!(!shown.contains("Case") && !shown.contains("Lid"));
+    }
+}
@@ -0,0 +1,13 @@
+pub mod devices;
+pub mod events;
+pub mod lid;
+pub mod merge;
+pub mod ownership;
+pub mod proximity;
+
+pub use devices::{Device, DeviceKey, DeviceTable};
+pub use events::{ChangeTracker, Event, EventKind};
+pub use lid::{LidEvent, LidTracker};
+pub use merge::{MergedState, PairMerger, Sourced};
+pub use ownership::{OwnershipResolver, PairedDevice, Proof};
+pub use proximity::{Measure, NearestSelector, RssiFilter};
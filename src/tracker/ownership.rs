@@ -0,0 +1,139 @@
+use crate::airpod::{IdentityResolver, Irk, ProximityKey};
+
+/// A device paired with this computer, with whatever is known to
+/// recognise its adverts.
+#[derive(Clone)]
+pub struct PairedDevice {
+    pub name: String,
+    /// Public address, as reported by the OS pairing.
+    pub address: Option<u64>,
+    pub irk: Option<Irk>,
+    /// Decrypts the exact battery levels. Not used to recognise adverts, a
+    /// wrong key passes the plaintext check too often to prove anything.
+    pub key: Option<ProximityKey>,
+}
+
+impl PairedDevice {
+    pub fn new(name: impl Into<String>) -> Self {
+        PairedDevice {
+            name: name.into(),
+            address: None,
+            irk: None,
+            key: None,
+        }
+    }
+
+    pub fn with_address(mut self, address: u64) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    pub fn with_irk(mut self, irk: Irk) -> Self {
+        self.irk = Some(irk);
+        self
+    }
+
+    pub fn with_key(mut self, key: ProximityKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Whether adverts can be linked to this device at all. AirPods
+    /// advertise from resolvable private addresses, which only the IRK ties
+    /// back to the public address of the pairing.
+    pub fn is_recognisable(&self) -> bool {
+        self.irk.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Proof {
+    /// The advert came from the public address of the pairing.
+    Address,
+    /// The resolvable private address was generated from the pairing's IRK.
+    Irk,
+}
+
+/// Tells the adverts of devices paired with this computer apart from
+/// everyone else's AirPods in range.
+#[derive(Clone, Default)]
+pub struct OwnershipResolver {
+    paired: Vec<PairedDevice>,
+    /// Index into `paired` of each device with an IRK.
+    identities: IdentityResolver<usize>,
+}
+
+impl OwnershipResolver {
+    pub fn new() -> Self {
+        OwnershipResolver {
+            paired: Vec::new(),
+            identities: IdentityResolver::new(),
+        }
+    }
+
+    pub fn add(&mut self, device: PairedDevice) {
+        if let Some(irk) = device.irk {
+            self.identities.add(self.paired.len(), irk);
+        }
+        self.paired.push(device);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paired.is_empty()
+    }
+
+    /// Whether any paired device can be recognised, see
+    /// `PairedDevice::is_recognisable`.
+    pub fn can_recognise(&self) -> bool {
+        self.paired.iter().any(PairedDevice::is_recognisable)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PairedDevice> {
+        self.paired.iter()
+    }
+
+    /// Returns the paired device an advert belongs to, if any.
+    pub fn owner(&self, address: u64) -> Option<&PairedDevice> {
+        self.recognise(address).map(|(device, _)| device)
+    }
+
+    /// Returns the paired device an advert belongs to and how it was linked.
+    pub fn recognise(&self, address: u64) -> Option<(&PairedDevice, Proof)> {
+        if let Some(device) = self
+            .paired
+            .iter()
+            .find(|device| device.address == Some(address))
+        {
+            return Some((device, Proof::Address));
+        }
+        let &index = self.identities.resolve(address)?;
+        Some((&self.paired[index], Proof::Irk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Resolvable private address generated from `irk`.
+    fn rpa(irk: &Irk, prand: u32) -> u64 {
+        let prand = (prand & 0x3F_FFFF) | 0x40_0000;
+        (u64::from(prand) << 24) | u64::from(irk.ah(prand))
+    }
+
+    #[test]
+    fn recognises_paired_addresses_and_their_private_addresses() {
+        let (first, second) = (Irk([0x01; 16]), Irk([0x02; 16]));
+        let mut owners = OwnershipResolver::new();
+        owners.add(PairedDevice::new("Keyboard").with_address(0xC0FF_EE00_0001));
+        owners.add(PairedDevice::new("First").with_irk(first));
+        owners.add(PairedDevice::new("Second").with_irk(second));
+        assert!(owners.can_recognise());
+
+        let (device, proof) = owners.recognise(0xC0FF_EE00_0001).unwrap();
+        assert_eq!((device.name.as_str(), proof), ("Keyboard", Proof::Address));
+        let (device, proof) = owners.recognise(rpa(&second, 7)).unwrap();
+        assert_eq!((device.name.as_str(), proof), ("Second", Proof::Irk));
+        assert!(owners.recognise(rpa(&Irk([0x03; 16]), 7)).is_none());
+    }
+}
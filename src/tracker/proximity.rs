@@ -0,0 +1,141 @@
+use crate::tracker::{Device, DeviceKey, DeviceTable};
+
+/// Exponential moving average of the RSSI of one device, which jumps by
+/// several dB from one advert to the next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RssiFilter {
+    alpha: f32,
+    value: Option<f32>,
+}
+
+impl RssiFilter {
+    /// Weight of a new sample; lower values smooth more but follow slower.
+    pub const DEFAULT_ALPHA: f32 = 0.2;
+
+    pub fn new(alpha: f32) -> Self {
+        RssiFilter {
+            alpha: alpha.clamp(0.0, 1.0),
+            value: None,
+        }
+    }
+
+    /// Folds in a new sample; the first one is taken as is.
+    pub fn update(&mut self, rssi: i16) -> f32 {
+        let rssi = f32::from(rssi);
+        let value = match self.value {
+            Some(value) => value + self.alpha * (rssi - value),
+            None => rssi,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+}
+
+impl Default for RssiFilter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_ALPHA)
+    }
+}
+
+/// What devices are compared by, the same for all of them since path loss
+/// and RSSI are different quantities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    /// Path loss, when every device advertises its transmit power.
+    PathLoss,
+    /// Smoothed RSSI otherwise.
+    Rssi,
+}
+
+impl Measure {
+    /// Path loss if every device with a signal advertises its transmit
+    /// power, RSSI otherwise.
+    pub fn common<'a>(devices: impl IntoIterator<Item = &'a Device>) -> Self {
+        let known = devices
+            .into_iter()
+            .filter(|device| device.smoothed_rssi().is_some())
+            .all(|device| device.tx_power.is_some());
+        if known {
+            Measure::PathLoss
+        } else {
+            Measure::Rssi
+        }
+    }
+}
+
+/// Follows the device closest to this machine by `Device::proximity`, using
+/// path loss only when every candidate advertises its transmit power (see
+/// `Measure::common`). Another device only takes over once it is closer
+/// by `margin` dB, so two pairs at similar distance don't flip back and
+/// forth.
+#[derive(Debug, Clone)]
+pub struct NearestSelector {
+    current: Option<DeviceKey>,
+    margin: f32,
+}
+
+impl NearestSelector {
+    pub const DEFAULT_MARGIN: f32 = 6.0;
+
+    pub fn new(margin: f32) -> Self {
+        NearestSelector {
+            current: None,
+            margin,
+        }
+    }
+
+    /// Picks the nearest device among `candidates`, sticking with the
+    /// current one unless it is gone or clearly farther away.
+    pub fn select<'a>(
+        &mut self,
+        candidates: impl IntoIterator<Item = &'a Device>,
+    ) -> Option<&'a Device> {
+        let candidates: Vec<_> = candidates.into_iter().collect();
+        let measure = Measure::common(candidates.iter().copied());
+        let mut current = None;
+        let mut nearest: Option<(&Device, f32)> = None;
+        for device in candidates {
+            let Some(proximity) = device.proximity(measure) else {
+                continue;
+            };
+            if self.current.as_ref() == Some(&device.key) {
+                current = Some((device, proximity));
+            }
+            if nearest.is_none_or(|(_, best)| proximity > best) {
+                nearest = Some((device, proximity));
+            }
+        }
+
+        let chosen = match (current, nearest) {
+            (Some((current, proximity)), Some((_, best))) if best - proximity < self.margin => {
+                current
+            }
+            (_, Some((nearest, _))) => nearest,
+            (_, None) => {
+                self.current = None;
+                return None;
+            }
+        };
+        self.current = Some(chosen.key.clone());
+        Some(chosen)
+    }
+
+    /// Picks the nearest device of `table`.
+    pub fn select_in<'a>(&mut self, table: &'a DeviceTable) -> Option<&'a Device> {
+        self.select(table.iter())
+    }
+
+    pub fn current(&self) -> Option<&DeviceKey> {
+        self.current.as_ref()
+    }
+}
+
+impl Default for NearestSelector {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MARGIN)
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use librepods_windows::airpod::{
+    Irk, ProximityKey, Registry,
+    continuity::{self, Message, TetheringSource},
+    registry,
+};
+#[cfg(all(target_os = "linux", feature = "bluez"))]
+use librepods_windows::backend::bluez::BluezSource;
+#[cfg(all(windows, feature = "windows"))]
+use librepods_windows::backend::windows::{self, WinRtSource};
+use librepods_windows::scan::{DeviceUpdate, ScanOptions, scan};
+use librepods_windows::source::{
+    Advertisement, AdvertisementSource, CaptureWriter, Recorder, ReplaySource, Sink,
+};
+use librepods_windows::tracker::{
+    Device, DeviceKey, NearestSelector, OwnershipResolver, PairedDevice,
+};
+
+/// Returns the value following `flag` on the command line, if any
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// Reads the proximity key passed with `--key <hex>`, if any
+fn key_from_args() -> Option<ProximityKey> {
+    let hex = arg_value("--key")?;
+    let key = ProximityKey::from_hex(&hex);
+    if key.is_none() {
+        eprintln!("Ignoring --key, expected 32 hex digits");
+    }
+    key
+}
+
+/// Reads the identity resolving key passed with `--irk <hex>`, if any
+fn irk_from_args() -> Option<Irk> {
+    let hex = arg_value("--irk")?;
+    let irk = Irk::from_hex(&hex);
+    if irk.is_none() {
+        eprintln!("Ignoring --irk, expected 32 hex digits");
+    }
+    irk
+}
+
+/// Scanner of the platform, and the devices paired with this computer
+#[cfg(all(windows, feature = "windows"))]
+fn platform_source() -> anyhow::Result<(Box<dyn AdvertisementSource + Send>, Vec<PairedDevice>)> {
+    let paired = windows::paired_devices().unwrap_or_else(|err| {
+        eprintln!("Could not list paired devices: {}", err);
+        Vec::new()
+    });
+    Ok((Box::new(WinRtSource::new()?), paired))
+}
+
+/// Scanner of the platform, and the devices paired with this computer
+#[cfg(all(target_os = "linux", feature = "bluez"))]
+fn platform_source() -> anyhow::Result<(Box<dyn AdvertisementSource + Send>, Vec<PairedDevice>)> {
+    let source = BluezSource::system()?;
+    let paired = source.paired_devices().unwrap_or_else(|err| {
+        eprintln!("Could not list paired devices: {:#}", err);
+        Vec::new()
+    });
+    Ok((Box::new(source), paired))
+}
+
+/// Replays the capture passed with `--replay <path>`, `--speed <x>` times
+/// faster than recorded
+fn replay_source(path: &str) -> anyhow::Result<Box<dyn AdvertisementSource + Send>> {
+    let speed = match arg_value("--speed") {
+        Some(speed) => speed.parse().unwrap_or_else(|_| {
+            eprintln!("Ignoring --speed, expected a number");
+            1.0
+        }),
+        None => 1.0,
+    };
+    Ok(Box::new(ReplaySource::load(path)?.with_speed(speed)))
+}
+
+/// Also writes everything received to the capture passed with
+/// `--record <path>`, scrubbed first with `--scrub`
+fn record_from_args(
+    source: Box<dyn AdvertisementSource + Send>,
+) -> anyhow::Result<Box<dyn AdvertisementSource + Send>> {
+    let Some(path) = arg_value("--record") else {
+        return Ok(source);
+    };
+    let mut writer = CaptureWriter::create(&path)?;
+    if std::env::args().any(|arg| arg == "--scrub") {
+        writer = writer.scrubbed();
+    }
+    Ok(Box::new(Recorder::new(source, writer)))
+}
+
+/// Builds the resolver for "my" devices from the OS pairings and the keys
+/// passed on the command line. The keys go to the first paired AirPods or
+/// Beats, or to a device of their own if there is none.
+fn owners_from_args(mut paired: Vec<PairedDevice>, key: Option<ProximityKey>) -> OwnershipResolver {
+    let irk = irk_from_args();
+    if irk.is_some() || key.is_some() {
+        let index = paired
+            .iter()
+            .position(|device| device.name.contains("AirPods") || device.name.contains("Beats"))
+            .unwrap_or_else(|| {
+                paired.push(PairedDevice::new("My AirPods"));
+                paired.len() - 1
+            });
+        paired[index].irk = irk;
+        paired[index].key = key;
+    }
+
+    let mut owners = OwnershipResolver::new();
+    for device in paired {
+        owners.add(device);
+    }
+    owners
+}
+
+/// Installs the models file passed with `--models <path>`, if any
+fn install_models_from_args() {
+    if let Some(path) = arg_value("--models") {
+        match Registry::load(&path) {
+            Ok(models) => {
+                if registry::install(models).is_err() {
+                    eprintln!("Ignoring --models: the model table is already in use");
+                }
+            }
+            Err(err) => eprintln!("Ignoring --models: {:#}", err),
+        }
+    }
+}
+
+/// Forwards advertisements unchanged, keeping the latest Instant Hotspot
+/// status of each advertiser on the way. Any phone in range may send one,
+/// only those from a paired address belong to the user.
+struct CompanionTap<S> {
+    inner: S,
+    phones: Arc<Mutex<BTreeMap<u64, TetheringSource>>>,
+}
+
+impl<S: AdvertisementSource> AdvertisementSource for CompanionTap<S> {
+    fn start(&mut self, mut sink: Sink) -> anyhow::Result<()> {
+        let phones = self.phones.clone();
+        self.inner.start(Box::new(move |advert: Advertisement| {
+            if let Some(data) = advert.apple_data() {
+                for message in continuity::decode(data) {
+                    if let Message::TetheringSource(source) = message {
+                        phones.lock().unwrap().insert(advert.address, source);
+                    }
+                }
+            }
+            sink(advert)
+        }))
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        self.inner.stop()
+    }
+}
+
+pub async fn run() -> anyhow::Result<()> {
+    let key = key_from_args();
+    install_models_from_args();
+
+    // Strangers' devices are only shown with `--all`
+    let show_all = std::env::args().any(|arg| arg == "--all");
+    let replay = arg_value("--replay");
+    let (source, paired) = match &replay {
+        Some(path) => (replay_source(path)?, Vec::new()),
+        None => platform_source()?,
+    };
+    let source = record_from_args(source)?;
+    let owners = owners_from_args(paired, key);
+    if owners.is_empty() && !show_all {
+        eprintln!("No paired devices or keys found, pass --all to show every device");
+    } else if !owners.can_recognise() && !show_all {
+        eprintln!(
+            "Paired devices can't be recognised without their IRK, \
+            pass --irk <hex>, or --all to show every device"
+        );
+    }
+
+    // Without an IRK, follow whichever AirPods are closest to this machine
+    let follow_nearest = !owners.can_recognise();
+    let mut selector = NearestSelector::default();
+
+    let phones = Arc::new(Mutex::new(BTreeMap::new()));
+    let source = CompanionTap {
+        inner: source,
+        phones: phones.clone(),
+    };
+    let options = ScanOptions {
+        owners: owners.clone(),
+        ..ScanOptions::default()
+    };
+    let mut updates = scan(source, options);
+
+    println!("Watching for BLE advertisements...");
+    // Live scans run for 30 seconds, replays until the capture ends
+    let run_for = match replay {
+        Some(_) => Duration::MAX,
+        None => Duration::from_secs(30),
+    };
+    let deadline = tokio::time::sleep(run_for);
+    tokio::pin!(deadline);
+    loop {
+        let update = tokio::select! {
+            update = updates.next() => update,
+            _ = &mut deadline => break,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        let (device, events) = match update.transpose()? {
+            Some(DeviceUpdate::Seen { device, events }) => (*device, events),
+            Some(DeviceUpdate::Lost { key, .. }) => {
+                println!("\nLost: {}", key);
+                continue;
+            }
+            None => break,
+        };
+
+        let devices = updates.devices();
+        let nearest = if follow_nearest {
+            selector.select_in(devices).map(|device| device.key.clone())
+        } else {
+            None
+        };
+        let shown =
+            |device: &Device| show_all || device.mine || nearest.as_ref() == Some(&device.key);
+        if !shown(&device) {
+            continue;
+        }
+
+        let mut info = format!(
+            "Device: {} ({} dBm, {:.0} dBm smoothed, {} adverts)\n",
+            device.key,
+            device.rssi.unwrap_or_default(),
+            device.smoothed_rssi().unwrap_or(f32::NAN),
+            device.adverts
+        );
+        info.push_str(&match &key {
+            Some(key) => device.last.debug_info_with_key(key),
+            None => device.last.debug_info(),
+        });
+        info.push_str(&format!("\n\nMerged State:\n{}", device.state()));
+        for event in events {
+            info.push_str(&format!("\nEvent: {:?}", event.kind));
+        }
+        for (&address, phone) in phones.lock().unwrap().iter() {
+            match owners.owner(address) {
+                Some(owner) => {
+                    info.push_str(&format!("\nCompanion Phone: {} - {}", owner.name, phone))
+                }
+                None if show_all => info.push_str(&format!(
+                    "\nHotspot {} (unverified): {}",
+                    DeviceKey::Address(address),
+                    phone
+                )),
+                None => {}
+            }
+        }
+        info.push_str(&format!("\n\nNearby Devices: {}", devices.len()));
+        for device in devices.ranked() {
+            if !shown(device) {
+                continue;
+            }
+            info.push_str(&format!(
+                "\n  {} - {} ({:.0} dBm)",
+                device.key,
+                device.last.model_name(),
+                device.smoothed_rssi().unwrap_or(f32::NAN)
+            ));
+        }
+        print!("\r{}", info);
+        io::stdout().flush().unwrap();
+    }
+
+    updates.stop()
+}